@@ -0,0 +1,88 @@
+use crate::error::{Error, Result};
+use std::fmt;
+
+/// Supervisor actions that can be bound to hotkeys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Restart,
+    Stop,
+    Start,
+    Kill,
+    Quit,
+    Clear,
+    Status,
+    Pause,
+    Dump,
+    ToggleFilter,
+    Help,
+    Palette,
+}
+
+impl Action {
+    pub const ALL: [Action; 12] = [
+        Action::Restart,
+        Action::Stop,
+        Action::Start,
+        Action::Kill,
+        Action::Quit,
+        Action::Clear,
+        Action::Status,
+        Action::Pause,
+        Action::Dump,
+        Action::ToggleFilter,
+        Action::Help,
+        Action::Palette,
+    ];
+
+    /// Parse an action name as used in `--bind key=action`
+    pub fn parse(name: &str) -> Result<Self> {
+        let action = match name.trim().to_lowercase().as_str() {
+            "restart" => Action::Restart,
+            "stop" => Action::Stop,
+            "start" => Action::Start,
+            "kill" | "force-kill" => Action::Kill,
+            "quit" | "exit" => Action::Quit,
+            "clear" => Action::Clear,
+            "status" => Action::Status,
+            "pause" => Action::Pause,
+            "dump" => Action::Dump,
+            "toggle-filter" | "filter" => Action::ToggleFilter,
+            "help" => Action::Help,
+            "palette" | "command" => Action::Palette,
+            _ => {
+                return Err(Error::InvalidValue {
+                    kind: "action",
+                    value: name.to_string(),
+                    reason: format!(
+                        "expected one of {}",
+                        Action::ALL.map(|a| a.name()).join(", ")
+                    ),
+                })
+            }
+        };
+        Ok(action)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Restart => "restart",
+            Action::Stop => "stop",
+            Action::Start => "start",
+            Action::Kill => "kill",
+            Action::Quit => "quit",
+            Action::Clear => "clear",
+            Action::Status => "status",
+            Action::Pause => "pause",
+            Action::Dump => "dump",
+            Action::ToggleFilter => "toggle-filter",
+            Action::Help => "help",
+            Action::Palette => "palette",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,133 @@
+use crate::session::{Frame, Registry};
+use anyhow::Result;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::io::{IsTerminal, Read, Write};
+use supi_cli::output::{print_line, print_line_colored, LogColor};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+/// Key that detaches `supi attach` from the session: Ctrl+]
+const DETACH_KEY: u8 = 0x1d;
+
+/// Restores the terminal when `supi attach` ends, however it ends
+struct RawModeGuard;
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
+
+/// `supi attach`: stream a detached session's output and send it this terminal's keys
+pub async fn attach(name: Option<&str>) -> Result<()> {
+    let session = Registry::open()?.find(name)?;
+    let stream = session.connect().await?;
+    let (mut reader, mut writer) = stream.into_split();
+
+    let interactive = std::io::stdin().is_terminal();
+    let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+    Frame::Attach { rows, cols }.write_to(&mut writer).await?;
+
+    let _raw_mode = if interactive {
+        enable_raw_mode()?;
+        Some(RawModeGuard)
+    } else {
+        None
+    };
+    print_line_colored(
+        &format!(
+            "[supi] Attached to session '{}' (PID {}), press Ctrl+] to detach",
+            session.name, session.pid
+        ),
+        LogColor::Green,
+    );
+
+    // Frames are read in their own task, a partly read frame can't be dropped by select!
+    let (frames_tx, mut frames) = mpsc::channel::<Frame>(64);
+    tokio::spawn(async move {
+        while let Ok(Some(frame)) = Frame::read_from(&mut reader).await {
+            if frames_tx.send(frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // Reading stdin blocks; a plain thread doesn't hold up the runtime on exit
+    let (keys_tx, mut keys) = mpsc::channel::<Vec<u8>>(64);
+    if interactive {
+        std::thread::spawn(move || {
+            let mut stdin = std::io::stdin();
+            let mut buf = [0u8; 1024];
+            while let Ok(n) = stdin.read(&mut buf) {
+                if n == 0 || keys_tx.blocking_send(buf[..n].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    let mut resized = signal(SignalKind::window_change())?;
+    let mut stdout = std::io::stdout();
+
+    let message = loop {
+        tokio::select! {
+            frame = frames.recv() => match frame {
+                Some(Frame::Output(bytes)) => {
+                    stdout.write_all(&bytes)?;
+                    stdout.flush()?;
+                }
+                Some(Frame::Closed(reason)) => break reason,
+                Some(_) => {}
+                None => break format!("[supi] Lost the connection to session '{}'", session.name),
+            },
+            Some(bytes) = keys.recv() => {
+                match bytes.iter().position(|b| *b == DETACH_KEY) {
+                    Some(detach) => {
+                        if detach > 0 {
+                            Frame::Input(bytes[..detach].to_vec()).write_to(&mut writer).await?;
+                        }
+                        break format!(
+                            "[supi] Detached from session '{}', it keeps running. Reattach with: supi attach {}",
+                            session.name, session.name
+                        );
+                    }
+                    None => Frame::Input(bytes).write_to(&mut writer).await?,
+                }
+            }
+            _ = resized.recv() => {
+                if let Ok((cols, rows)) = crossterm::terminal::size() {
+                    Frame::Resize { rows, cols }.write_to(&mut writer).await?;
+                }
+            }
+        }
+    };
+
+    print_line("");
+    print_line_colored(&message, LogColor::Green);
+    Ok(())
+}
+
+/// `supi detach`: disconnect every terminal attached to a session, leaving it running
+pub async fn detach(name: Option<&str>) -> Result<()> {
+    let session = Registry::open()?.find(name)?;
+    let stream = session.connect().await?;
+    let (mut reader, mut writer) = stream.into_split();
+
+    Frame::Detach.write_to(&mut writer).await?;
+    let count = match Frame::read_from(&mut reader).await? {
+        Some(Frame::Closed(count)) => count,
+        _ => anyhow::bail!(
+            "Session '{}' didn't answer the detach request",
+            session.name
+        ),
+    };
+
+    print_line_colored(
+        &format!(
+            "[supi] Detached {} terminal(s) from session '{}' (PID {}), it keeps running",
+            count, session.name, session.pid
+        ),
+        LogColor::Yellow,
+    );
+    Ok(())
+}
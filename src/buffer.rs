@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Which child stream a line came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl fmt::Display for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stream::Stdout => write!(f, "stdout"),
+            Stream::Stderr => write!(f, "stderr"),
+        }
+    }
+}
+
+/// A single line of child output, tagged with its stream and run number
+#[derive(Debug, Clone)]
+pub struct BufferedLine {
+    pub run: u64,
+    pub stream: Stream,
+    pub line: String,
+}
+
+struct BufferState {
+    lines: VecDeque<BufferedLine>,
+    bytes: usize,
+}
+
+/// In-memory ring buffer holding the most recent child output.
+/// Bounded by both a line count and a byte size; the oldest lines are
+/// evicted first. Cheap to clone (shared state).
+#[derive(Clone)]
+pub struct OutputBuffer {
+    state: Arc<Mutex<BufferState>>,
+    max_lines: usize,
+    max_bytes: usize,
+}
+
+impl OutputBuffer {
+    /// Create a new buffer. A `max_lines` or `max_bytes` of 0 disables buffering.
+    pub fn new(max_lines: usize, max_bytes: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BufferState {
+                lines: VecDeque::new(),
+                bytes: 0,
+            })),
+            max_lines,
+            max_bytes,
+        }
+    }
+
+    /// Whether the buffer keeps any output at all
+    pub fn is_enabled(&self) -> bool {
+        self.max_lines > 0 && self.max_bytes > 0
+    }
+
+    /// Append a line, evicting the oldest lines when a limit is exceeded
+    pub fn push(&self, run: u64, stream: Stream, line: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.bytes += line.len();
+        state.lines.push_back(BufferedLine {
+            run,
+            stream,
+            line: line.to_string(),
+        });
+
+        while state.lines.len() > self.max_lines || state.bytes > self.max_bytes {
+            match state.lines.pop_front() {
+                Some(old) => state.bytes -= old.line.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Get the last `count` lines of a stream for a specific run (oldest first)
+    pub fn last_lines(&self, run: u64, stream: Stream, count: usize) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let mut lines: Vec<String> = state
+            .lines
+            .iter()
+            .rev()
+            .filter(|l| l.run == run && l.stream == stream)
+            .take(count)
+            .map(|l| l.line.clone())
+            .collect();
+        lines.reverse();
+        lines
+    }
+
+    /// Write the whole buffer to a file, returning the number of lines written.
+    /// Each line is prefixed with its run number and stream.
+    pub fn dump_to_file(&self, path: &Path) -> Result<usize> {
+        let snapshot: Vec<BufferedLine> = {
+            let state = self.state.lock().unwrap();
+            state.lines.iter().cloned().collect()
+        };
+
+        let mut file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create dump file {}", path.display()))?;
+        for entry in &snapshot {
+            writeln!(
+                file,
+                "[run {}] [{}] {}",
+                entry.run, entry.stream, entry.line
+            )
+            .with_context(|| format!("Failed to write dump file {}", path.display()))?;
+        }
+
+        Ok(snapshot.len())
+    }
+}
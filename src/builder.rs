@@ -0,0 +1,544 @@
+use crate::buffer::OutputBuffer;
+use crate::env::Environment;
+use crate::error::{Error, Result};
+use crate::events::Events;
+use crate::filter::{FilterRule, OutputFilter};
+use crate::highlight::{self, HighlightRule, Highlighter};
+use crate::hotkey::Keymap;
+use crate::keys::KeySequence;
+use crate::launch::LaunchOptions;
+use crate::limits::ResourceLimits;
+use crate::output::{LogColor, Output};
+use crate::process::ProcessManager;
+use crate::schedule::RestartSchedule;
+use crate::signals::SignalHandler;
+use crate::supervisor::Supervisor;
+use crate::triggers::{TriggerMatch, TriggerRule, TriggerSet};
+use crate::units;
+use crate::watchdog::Watchdog;
+use anyhow::Context;
+use std::path::PathBuf;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+/// Configures a [`Supervisor`]. Every setting matches the `supi` option of the same
+/// name and takes values in the same syntax; [`build`](Self::build) validates them.
+#[derive(Debug, Clone)]
+pub struct SupervisorBuilder {
+    command: String,
+    args: Vec<String>,
+    stop_on_child_exit: bool,
+    subreaper: bool,
+    restart_signal: String,
+    forward_signals: Vec<String>,
+    on_signals: Vec<String>,
+    hotkeys: bool,
+    restart_hotkey: KeySequence,
+    dump_hotkey: KeySequence,
+    filter_hotkey: KeySequence,
+    binds: Vec<String>,
+    log_color: LogColor,
+    info_color: LogColor,
+    silent: bool,
+    restart_debounce_ms: u64,
+    buffer_lines: usize,
+    buffer_mb: usize,
+    dump_file: PathBuf,
+    dump_signal: Option<String>,
+    crash_summary_lines: usize,
+    highlights: Vec<String>,
+    highlight_preset: Option<String>,
+    hide: Vec<String>,
+    only: Vec<String>,
+    triggers: Vec<String>,
+    envs: Vec<String>,
+    env_files: Vec<PathBuf>,
+    env_command: Option<String>,
+    clear_env: bool,
+    keep_env: Vec<String>,
+    cwd: Option<PathBuf>,
+    umask: Option<String>,
+    user: Option<String>,
+    group: Option<String>,
+    limits: Vec<String>,
+    nice: Option<i32>,
+    ionice: Option<String>,
+    cpu_affinity: Option<String>,
+    max_memory: Option<String>,
+    max_cpu: Option<String>,
+    max_cpu_for: String,
+    sample_interval: String,
+    max_runtime: Option<String>,
+    restart_every: Option<String>,
+    restart_cron: Option<String>,
+    start_delay: Option<String>,
+    autostart: bool,
+}
+
+/// Generate a setter for each builder field
+macro_rules! setters {
+    ($($(#[$doc:meta])* $name:ident: $ty:ty;)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name(mut self, $name: $ty) -> Self {
+                self.$name = $name.into();
+                self
+            }
+        )*
+    };
+}
+
+/// Generate a setter for each optional builder field, which is unset by default
+macro_rules! option_setters {
+    ($($(#[$doc:meta])* $name:ident: $ty:ty;)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name(mut self, $name: $ty) -> Self {
+                self.$name = Some($name.into());
+                self
+            }
+        )*
+    };
+}
+
+/// Generate a setter that takes any list of values for each repeatable field
+macro_rules! list_setters {
+    ($($(#[$doc:meta])* $name:ident: $item:ty;)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name<I>(mut self, $name: I) -> Self
+            where
+                I: IntoIterator,
+                I::Item: Into<$item>,
+            {
+                self.$name = $name.into_iter().map(Into::into).collect();
+                self
+            }
+        )*
+    };
+}
+
+impl SupervisorBuilder {
+    /// Supervise `command`, with the same defaults as `supi`
+    pub fn new(command: impl Into<String>) -> Self {
+        let key = |spec: &str| spec.parse().expect("default hotkeys are valid");
+        Self {
+            command: command.into(),
+            args: Vec::new(),
+            stop_on_child_exit: false,
+            subreaper: false,
+            restart_signal: "SIGUSR1".to_string(),
+            forward_signals: Vec::new(),
+            on_signals: Vec::new(),
+            hotkeys: true,
+            restart_hotkey: key("r"),
+            dump_hotkey: key("d"),
+            filter_hotkey: key("f"),
+            binds: Vec::new(),
+            log_color: LogColor::Yellow,
+            info_color: LogColor::Green,
+            silent: false,
+            restart_debounce_ms: 1000,
+            buffer_lines: 1000,
+            buffer_mb: 1,
+            dump_file: PathBuf::from("supi-output.log"),
+            dump_signal: None,
+            crash_summary_lines: 10,
+            highlights: Vec::new(),
+            highlight_preset: None,
+            hide: Vec::new(),
+            only: Vec::new(),
+            triggers: Vec::new(),
+            envs: Vec::new(),
+            env_files: Vec::new(),
+            env_command: None,
+            clear_env: false,
+            keep_env: Vec::new(),
+            cwd: None,
+            umask: None,
+            user: None,
+            group: None,
+            limits: Vec::new(),
+            nice: None,
+            ionice: None,
+            cpu_affinity: None,
+            max_memory: None,
+            max_cpu: None,
+            max_cpu_for: "30s".to_string(),
+            sample_interval: "2s".to_string(),
+            max_runtime: None,
+            restart_every: None,
+            restart_cron: None,
+            start_delay: None,
+            autostart: true,
+        }
+    }
+
+    setters! {
+        /// Exit when the child exits instead of waiting for a restart
+        stop_on_child_exit: bool;
+        /// Reap orphaned descendants as a child subreaper (implied when running as PID 1)
+        subreaper: bool;
+        /// Signal that restarts the child (default: SIGUSR1)
+        restart_signal: impl Into<String>;
+        /// Read hotkeys from the terminal when stdin is one (default: true)
+        hotkeys: bool;
+        /// Hotkey that restarts the child (default: `r`)
+        restart_hotkey: KeySequence;
+        /// Hotkey that dumps the output buffer (default: `d`)
+        dump_hotkey: KeySequence;
+        /// Hotkey that toggles output filters (default: `f`)
+        filter_hotkey: KeySequence;
+        /// Color of supervisor messages (default: yellow)
+        log_color: LogColor;
+        /// Color of informational messages (default: green)
+        info_color: LogColor;
+        /// Suppress supervisor messages
+        silent: bool;
+        /// Ignore restart requests within this many milliseconds of the last one (default: 1000)
+        restart_debounce_ms: u64;
+        /// Lines of child output kept for dumps and crash summaries (default: 1000)
+        buffer_lines: usize;
+        /// Size limit of the output buffer in MB (default: 1)
+        buffer_mb: usize;
+        /// File the output buffer is dumped to (default: supi-output.log)
+        dump_file: impl Into<PathBuf>;
+        /// Stderr lines shown when the child fails (default: 10)
+        crash_summary_lines: usize;
+        /// Start the child with an empty environment, except `keep_env`
+        clear_env: bool;
+        /// How long CPU use has to stay above `max_cpu` (default: 30s)
+        max_cpu_for: impl Into<String>;
+        /// How often resource use is sampled (default: 2s)
+        sample_interval: impl Into<String>;
+        /// Start the child right away, instead of on the first restart (default: true)
+        autostart: bool;
+    }
+
+    option_setters! {
+        /// Signal that dumps the output buffer
+        dump_signal: impl Into<String>;
+        /// Built-in highlight rules, e.g. `logs`
+        highlight_preset: impl Into<String>;
+        /// Command whose `KEY=VALUE` output lines are added to the child environment
+        env_command: impl Into<String>;
+        /// Working directory of the child
+        cwd: impl Into<PathBuf>;
+        /// File mode creation mask of the child, e.g. `022`
+        umask: impl Into<String>;
+        /// User to run the child as
+        user: impl Into<String>;
+        /// Group to run the child as
+        group: impl Into<String>;
+        /// Scheduling priority of the child
+        nice: i32;
+        /// I/O scheduling class of the child, e.g. `idle` or `best-effort:7`
+        ionice: impl Into<String>;
+        /// CPUs the child may run on, e.g. `0-3,6`
+        cpu_affinity: impl Into<String>;
+        /// Restart the child when its memory use exceeds this size, e.g. `512M`
+        max_memory: impl Into<String>;
+        /// Restart the child when its CPU use stays above this percentage
+        max_cpu: impl Into<String>;
+        /// Stop each run after this duration, e.g. `30m`
+        max_runtime: impl Into<String>;
+        /// Restart the child at a fixed interval, e.g. `6h`
+        restart_every: impl Into<String>;
+        /// Restart the child on a cron schedule, e.g. `0 4 * * *`
+        restart_cron: impl Into<String>;
+        /// Wait this long before the first start, e.g. `5s`
+        start_delay: impl Into<String>;
+    }
+
+    list_setters! {
+        /// Arguments of the command
+        args: String;
+        /// Signals relayed to the child process group
+        forward_signals: String;
+        /// `SIGNAL[,SIGNAL...]=ACTION` mappings
+        on_signals: String;
+        /// `KEY=ACTION` hotkey bindings
+        binds: String;
+        /// `REGEX=COLOR` highlight rules for child output
+        highlights: String;
+        /// Regexes of child output lines to hide
+        hide: String;
+        /// Regexes of the only child output lines to show
+        only: String;
+        /// Output trigger specs, e.g. `restart:panicked`
+        triggers: String;
+        /// `KEY=VALUE` variables for the child
+        envs: String;
+        /// Env files whose variables are added to the child environment
+        env_files: PathBuf;
+        /// Variables kept with `clear_env`
+        keep_env: String;
+        /// `NAME=VALUE` resource limits, e.g. `nofile=4096`
+        limits: String;
+    }
+
+    /// Validate the settings and create the supervisor, logging a summary of the
+    /// non-default ones. Must be called inside a tokio runtime.
+    pub fn build(self) -> Result<Supervisor> {
+        let output = self.output()?;
+        let init_mode = self.init_mode();
+        let limits = self.resource_limits(&output)?;
+
+        let watchdog = if self.max_memory.is_some() || self.max_cpu.is_some() {
+            let watchdog = Watchdog::new(
+                self.max_memory.as_deref(),
+                self.max_cpu.as_deref(),
+                &self.max_cpu_for,
+                &self.sample_interval,
+            )?;
+            output.log(&format!("[supi] Watchdog: {}", watchdog.describe()));
+            Some(watchdog)
+        } else {
+            None
+        };
+
+        let max_runtime = self
+            .max_runtime
+            .as_deref()
+            .map(|value| {
+                units::parse_duration(value)
+                    .ok()
+                    .filter(|runtime| !runtime.is_zero())
+                    .ok_or_else(|| {
+                        Error::invalid_option("max_runtime", value, "expected a duration like 30m")
+                    })
+            })
+            .transpose()?;
+        if let Some(max_runtime) = max_runtime {
+            output.log(&format!(
+                "[supi] Max runtime: {} per run, then {}",
+                units::format_duration(max_runtime),
+                if self.stop_on_child_exit {
+                    "exit with code 124"
+                } else {
+                    "restart"
+                }
+            ));
+        }
+
+        let start_delay = self
+            .start_delay
+            .as_deref()
+            .map(|value| {
+                units::parse_duration(value)
+                    .and_then(|delay| {
+                        // The first start is scheduled as a point in time
+                        Instant::now()
+                            .checked_add(delay)
+                            .map(|_| delay)
+                            .context("too large")
+                    })
+                    .map_err(|e| Error::invalid_option("start_delay", value, e))
+            })
+            .transpose()?;
+
+        let schedule = match (&self.restart_every, &self.restart_cron) {
+            (Some(every), _) => Some(RestartSchedule::every(every)?),
+            (None, Some(pattern)) => Some(RestartSchedule::cron(pattern)?),
+            (None, None) => None,
+        };
+        if let Some(schedule) = &schedule {
+            output.log(&format!(
+                "[supi] Restart schedule: {}, next restart at {}",
+                schedule.describe(),
+                schedule.describe_next()
+            ));
+        }
+
+        let keymap = Keymap::new(
+            &self.restart_hotkey,
+            &self.dump_hotkey,
+            &self.filter_hotkey,
+            &self.binds,
+        )?;
+
+        let (process_manager, filter, buffer, trigger_matches) =
+            self.process_manager(&output, limits)?;
+
+        if init_mode {
+            output.log(&format!(
+                "[supi] Init mode: {}, reaping orphaned processes",
+                if std::process::id() == 1 {
+                    "running as PID 1"
+                } else {
+                    "child subreaper enabled"
+                }
+            ));
+        }
+
+        let signal_handler = SignalHandler::new(
+            &self.restart_signal,
+            self.dump_signal.as_deref(),
+            &self.forward_signals,
+            &self.on_signals,
+            init_mode,
+        )?;
+        if init_mode || !self.on_signals.is_empty() || !self.forward_signals.is_empty() {
+            output.log(&format!(
+                "[supi] Signal actions: {}",
+                signal_handler.describe()
+            ));
+        }
+
+        Ok(Supervisor::new(
+            process_manager,
+            signal_handler,
+            self.hotkeys,
+            self.stop_on_child_exit,
+            self.restart_signal,
+            keymap,
+            output,
+            self.restart_debounce_ms,
+            buffer,
+            self.dump_file,
+            self.crash_summary_lines,
+            trigger_matches,
+            filter,
+            init_mode,
+            watchdog,
+            max_runtime,
+            schedule,
+            self.autostart,
+            start_delay,
+        ))
+    }
+
+    /// Validate the settings for the child and create just its process manager, to
+    /// start and stop it without the supervisor event loop
+    pub fn build_process_manager(self) -> Result<ProcessManager> {
+        let output = self.output()?;
+        let limits = self.resource_limits(&output)?;
+        let (process_manager, ..) = self.process_manager(&output, limits)?;
+        Ok(process_manager)
+    }
+
+    /// As PID 1 supi inherits every orphan in the container, so init mode is implied
+    fn init_mode(&self) -> bool {
+        self.subreaper || std::process::id() == 1
+    }
+
+    /// The output for supervisor messages and child output, with the highlight rules
+    fn output(&self) -> Result<Output> {
+        // Explicit highlight rules come first, so they win over the preset
+        let mut highlight_rules = self
+            .highlights
+            .iter()
+            .map(|spec| {
+                HighlightRule::parse(spec).map_err(|e| Error::invalid_option("highlights", spec, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(preset) = &self.highlight_preset {
+            highlight_rules.extend(
+                highlight::preset(preset)
+                    .map_err(|e| Error::invalid_option("highlight_preset", preset, e))?,
+            );
+        }
+
+        let output = Output::new(self.log_color, self.info_color, self.silent);
+        if highlight_rules.is_empty() {
+            Ok(output)
+        } else {
+            Ok(output.with_highlighter(Highlighter::new(highlight_rules)))
+        }
+    }
+
+    /// Resource limits and scheduling settings applied to the child
+    fn resource_limits(&self, output: &Output) -> Result<ResourceLimits> {
+        let limits = ResourceLimits::new(
+            &self.limits,
+            self.nice,
+            self.ionice.as_deref(),
+            self.cpu_affinity.as_deref(),
+        )?;
+        if let Some(limits) = limits.describe() {
+            output.log(&format!("[supi] Limits: {}", limits));
+        }
+        Ok(limits)
+    }
+
+    /// The process manager, with the output filter, buffer and trigger matches the
+    /// supervisor shares with it
+    #[allow(clippy::type_complexity)]
+    fn process_manager(
+        &self,
+        output: &Output,
+        limits: ResourceLimits,
+    ) -> Result<(
+        ProcessManager,
+        OutputFilter,
+        OutputBuffer,
+        mpsc::UnboundedReceiver<TriggerMatch>,
+    )> {
+        let parse_filters = |option: &'static str, specs: &[String]| {
+            specs
+                .iter()
+                .map(|spec| {
+                    FilterRule::parse(spec).map_err(|e| Error::invalid_option(option, spec, e))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let filter = OutputFilter::new(
+            parse_filters("hide", &self.hide)?,
+            parse_filters("only", &self.only)?,
+        );
+
+        let trigger_rules = self
+            .triggers
+            .iter()
+            .map(|spec| {
+                TriggerRule::parse(spec).map_err(|e| Error::invalid_option("triggers", spec, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (triggers, trigger_matches) = TriggerSet::new(trigger_rules);
+        if triggers.rule_count() > 0 {
+            output.log(&format!(
+                "[supi] Output triggers: {}",
+                self.triggers.join(" | ")
+            ));
+        }
+
+        let environment = Environment::new(
+            &self.envs,
+            self.env_files.clone(),
+            self.env_command.clone(),
+            self.clear_env,
+            self.keep_env.clone(),
+        )?;
+        if let Some(sources) = environment.describe() {
+            output.log(&format!("[supi] Child environment: {}", sources));
+        }
+
+        let launch = LaunchOptions::new(
+            self.cwd.clone(),
+            self.umask.as_deref(),
+            self.user.as_deref(),
+            self.group.as_deref(),
+        )?
+        .with_limits(limits);
+        if let Some(settings) = launch.describe() {
+            output.log(&format!("[supi] Child process settings: {}", settings));
+        }
+
+        let buffer_bytes = self.buffer_mb.checked_mul(1024 * 1024).ok_or_else(|| {
+            Error::invalid_option("buffer_mb", self.buffer_mb.to_string(), "too large")
+        })?;
+        let buffer = OutputBuffer::new(self.buffer_lines, buffer_bytes);
+        let process_manager = ProcessManager::new(
+            self.command.clone(),
+            self.args.clone(),
+            output.clone(),
+            buffer.clone(),
+            triggers,
+            filter.clone(),
+            environment,
+            launch,
+            Events::new(),
+        );
+        Ok((process_manager, filter, buffer, trigger_matches))
+    }
+}
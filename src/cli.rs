@@ -1,30 +1,68 @@
-use clap::Parser;
+use crate::completions::{self, Shell};
+use clap::builder::BoolishValueParser;
+use clap::{ArgAction, Command, CommandFactory, Parser, Subcommand, ValueHint};
+use clap_complete::engine::{ArgValueCandidates, ArgValueCompleter};
+use std::path::PathBuf;
+use supi_cli::KeySequence;
 
 #[derive(Parser, Debug)]
 #[command(name = "supi")]
 #[command(version)]
 #[command(about = "A lightweight process supervisor with restart capabilities", long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub subcommand: Option<Commands>,
+
     /// Stop the supervisor when the child process exits
     #[arg(long)]
     pub stop_on_child_exit: bool,
 
+    /// Run in init mode: become a child subreaper, reap orphaned descendants, forward
+    /// SIGHUP, SIGUSR2 and SIGWINCH to the child and exit with the child's status.
+    /// Enabled automatically when supi runs as PID 1 (e.g. as a container entrypoint)
+    #[arg(long)]
+    pub subreaper: bool,
+
     /// Signal to use for restarting the child process (default: SIGUSR1)
-    #[arg(long, default_value = "SIGUSR1")]
+    #[arg(long, default_value = "SIGUSR1", add = ArgValueCandidates::new(completions::signals))]
     pub restart_signal: String,
 
-    /// Hotkey character for manual restart (default: 'r')
-    #[arg(long, default_value = "r")]
-    pub restart_hotkey: char,
+    /// Forward signals received by supi to the child process group (repeatable, comma-separated).
+    /// Shorthand for --on-signal <SIGNAL>=forward, e.g. --forward-signal SIGHUP,SIGUSR2
+    #[arg(
+        long = "forward-signal",
+        value_name = "SIGNAL",
+        value_delimiter = ',',
+        add = ArgValueCandidates::new(completions::signals)
+    )]
+    pub forward_signals: Vec<String>,
+
+    /// Map signals to actions (repeatable). Format: <SIGNAL>[,<SIGNAL>...]=<action>
+    /// Actions: terminate, forward, ignore, or any hotkey action (restart, stop, dump, ...)
+    /// Any catchable signal works, including realtime signals like SIGRTMIN+3
+    /// Example: --on-signal SIGHUP,SIGWINCH=forward --on-signal SIGRTMIN+3=restart
+    #[arg(
+        long = "on-signal",
+        value_name = "SIGNAL=ACTION",
+        add = ArgValueCompleter::new(completions::signal_mappings)
+    )]
+    pub on_signals: Vec<String>,
+
+    /// Hotkey for manual restart (default: 'r').
+    /// Accepts a character, named keys and modifiers (e.g. ctrl+r, f5, alt+shift+x)
+    /// or a leader sequence separated by spaces (e.g. "space r")
+    #[arg(long, default_value = "r", value_name = "KEY")]
+    pub restart_hotkey: KeySequence,
 
     /// Color for supervisor log messages (default: yellow)
     /// Supported: yellow, red, green, blue, cyan, magenta, white, none
-    #[arg(long, default_value = "yellow")]
+    #[arg(long, default_value = "yellow", add = ArgValueCandidates::new(completions::colors))]
     pub log_color: String,
 
     /// Color for informational messages (default: green)
     /// Supported: yellow, red, green, blue, cyan, magenta, white, none
-    #[arg(long, default_value = "green")]
+    #[arg(long, default_value = "green", add = ArgValueCandidates::new(completions::colors))]
     pub info_color: String,
 
     /// Suppress all supervisor output (child process output still visible)
@@ -35,11 +73,272 @@ pub struct Cli {
     #[arg(long, default_value = "1000")]
     pub restart_debounce_ms: u64,
 
+    /// Number of recent child output lines kept in memory (default: 1000). Set to 0 to disable.
+    #[arg(long, default_value = "1000")]
+    pub buffer_lines: usize,
+
+    /// Maximum size of the in-memory output buffer in megabytes (default: 1)
+    #[arg(long, default_value = "1")]
+    pub buffer_mb: usize,
+
+    /// File the output buffer is written to on dump (default: supi-output.log)
+    #[arg(long, default_value = "supi-output.log", value_hint = ValueHint::FilePath)]
+    pub dump_file: PathBuf,
+
+    /// Hotkey for dumping the output buffer to the dump file (default: 'd')
+    #[arg(long, default_value = "d", value_name = "KEY")]
+    pub dump_hotkey: KeySequence,
+
+    /// Signal that dumps the output buffer to the dump file (e.g., SIGUSR2)
+    #[arg(long, add = ArgValueCandidates::new(completions::signals))]
+    pub dump_signal: Option<String>,
+
+    /// Number of stderr lines shown in the summary when the child fails (default: 10). Set to 0 to disable.
+    #[arg(long, default_value = "10")]
+    pub crash_summary_lines: usize,
+
+    /// Colorize regex matches in child output (repeatable). Format: <regex>=<color>
+    /// Example: --highlight 'error|panic=red' --highlight 'warn=yellow'
+    #[arg(
+        long = "highlight",
+        value_name = "REGEX=COLOR",
+        add = ArgValueCompleter::new(completions::highlights)
+    )]
+    pub highlights: Vec<String>,
+
+    /// Built-in highlight rules for common log formats. Supported: logs
+    #[arg(long, add = ArgValueCandidates::new(completions::highlight_presets))]
+    pub highlight_preset: Option<String>,
+
+    /// Bind a hotkey to an action (repeatable). Format: <key>=<action>, use <key>=none to unbind.
+    /// Actions: restart, stop, start, kill, quit, clear, status, pause, dump, toggle-filter
+    /// Defaults: s=stop, k=kill, q=quit, l=clear, i=status, p=pause (plus the --*-hotkey flags)
+    #[arg(
+        long = "bind",
+        value_name = "KEY=ACTION",
+        add = ArgValueCompleter::new(completions::bindings)
+    )]
+    pub binds: Vec<String>,
+
+    /// Hide child output lines matching a regex (repeatable). Prefix with stdout: or stderr:
+    /// to only filter one stream. Hidden lines still reach the output buffer and triggers.
+    #[arg(long = "hide", value_name = "REGEX")]
+    pub hide: Vec<String>,
+
+    /// Only show child output lines matching a regex (repeatable). Prefix with stdout: or
+    /// stderr: to only filter one stream.
+    #[arg(long = "only", value_name = "REGEX")]
+    pub only: Vec<String>,
+
+    /// Hotkey for toggling output filters on and off (default: 'f')
+    #[arg(long, default_value = "f", value_name = "KEY")]
+    pub filter_hotkey: KeySequence,
+
+    /// Run an action when child output matches a regex (repeatable).
+    /// Format: <action>[,<option>...]:<regex>
+    /// Actions: restart, stop, exit(N), run(COMMAND), notify
+    /// Options: stdout, stderr, cooldown=<duration>, max=<count>
+    /// Example: --trigger 'restart,stderr,cooldown=10s:EADDRINUSE'
+    #[arg(long = "trigger", value_name = "SPEC")]
+    pub triggers: Vec<String>,
+
+    /// Set an environment variable for the child (repeatable). Format: KEY=VALUE
+    #[arg(long = "env", value_name = "KEY=VALUE")]
+    pub envs: Vec<String>,
+
+    /// Load environment variables from a dotenv file (repeatable). Supports quotes,
+    /// comments, `export` and $VAR / ${VAR:-default} expansion. Re-read on every restart
+    #[arg(long = "env-file", value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub env_files: Vec<PathBuf>,
+
+    /// Load environment variables from the KEY=VALUE output of a shell command, run
+    /// before every start. Example: --env-command 'direnv exec . env'
+    #[arg(long, value_name = "COMMAND")]
+    pub env_command: Option<String>,
+
+    /// Don't pass supi's environment on to the child, except variables named with --keep-env
+    #[arg(long)]
+    pub clear_env: bool,
+
+    /// Inherited variables to keep with --clear-env (repeatable, comma-separated).
+    /// A trailing * matches a prefix, e.g. --keep-env PATH,HOME,LC_*
+    #[arg(
+        long = "keep-env",
+        value_name = "VAR",
+        value_delimiter = ',',
+        requires = "clear_env"
+    )]
+    pub keep_env: Vec<String>,
+
+    /// Working directory for the child (default: supi's working directory)
+    #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+    pub cwd: Option<PathBuf>,
+
+    /// File mode creation mask for the child, in octal (e.g. 022 or 0077)
+    #[arg(long, value_name = "MODE")]
+    pub umask: Option<String>,
+
+    /// Run the child as this user (name or uid). Requires supi to run as root.
+    /// Sets the user's supplementary groups, HOME, USER and LOGNAME
+    #[arg(long, value_name = "USER")]
+    pub user: Option<String>,
+
+    /// Run the child with this primary group (name or gid, default: the user's group).
+    /// Requires supi to run as root
+    #[arg(long, value_name = "GROUP")]
+    pub group: Option<String>,
+
+    /// Resource limit for the child, set with setrlimit before exec (repeatable,
+    /// comma-separated). Format: <NAME>=<VALUE> or <NAME>=<SOFT>:<HARD>, sizes accept
+    /// K/M/G/T, cpu accepts a duration, and any value can be 'unlimited'.
+    /// Example: --limit nofile=4096,as=2G,cpu=10m,core=unlimited
+    #[arg(long = "limit", value_name = "NAME=VALUE", value_delimiter = ',')]
+    pub limits: Vec<String>,
+
+    /// Scheduling priority (nice value) for the child, from -20 to 19.
+    /// Negative values need root
+    #[arg(long, value_name = "N", allow_negative_numbers = true)]
+    pub nice: Option<i32>,
+
+    /// I/O scheduling class for the child (Linux). Format: <CLASS>[:<LEVEL>]
+    /// Classes: realtime, best-effort, idle; levels 0 (highest) to 7. Example: best-effort:7
+    #[arg(long, value_name = "CLASS[:LEVEL]")]
+    pub ionice: Option<String>,
+
+    /// Pin the child to these CPUs (Linux). Example: --cpu-affinity 0-3,6
+    #[arg(long, value_name = "CPUS")]
+    pub cpu_affinity: Option<String>,
+
+    /// Restart the child when its process tree uses more memory (RSS) than this.
+    /// Accepts K/M/G/T, e.g. --max-memory 1.5G
+    #[arg(long, value_name = "SIZE")]
+    pub max_memory: Option<String>,
+
+    /// Restart the child when its process tree uses more CPU than this for
+    /// --max-cpu-for. 100% is one core, e.g. --max-cpu 95%
+    #[arg(long, value_name = "PERCENT")]
+    pub max_cpu: Option<String>,
+
+    /// How long CPU usage has to stay above --max-cpu before restarting (default: 30s)
+    #[arg(long, default_value = "30s", value_name = "DURATION")]
+    pub max_cpu_for: String,
+
+    /// How often --max-memory and --max-cpu sample the child's process tree (default: 2s)
+    #[arg(long, default_value = "2s", value_name = "DURATION")]
+    pub sample_interval: String,
+
+    /// Gracefully stop each run after this long, e.g. --max-runtime 30m. The child is
+    /// then restarted, or supi exits with code 124 when --stop-on-child-exit is set
+    #[arg(long, value_name = "DURATION")]
+    pub max_runtime: Option<String>,
+
+    /// Restart the child on a fixed interval, counted from supi's start, e.g.
+    /// --restart-every 6h. Goes through the restart debounce like any other restart
+    #[arg(long, value_name = "DURATION", conflicts_with = "restart_cron")]
+    pub restart_every: Option<String>,
+
+    /// Restart the child on a cron schedule in local time: minute hour day month
+    /// weekday, with optional leading seconds. Example: --restart-cron '0 3 * * *'
+    #[arg(long, value_name = "PATTERN")]
+    pub restart_cron: Option<String>,
+
+    /// Wait this long before starting the child for the first time, e.g. --start-delay 5s.
+    /// A restart hotkey or signal starts it right away
+    #[arg(long, value_name = "DURATION", conflicts_with = "no_autostart")]
+    pub start_delay: Option<String>,
+
+    /// Don't start the child when supi starts; the restart hotkey or signal starts it
+    #[arg(long)]
+    pub no_autostart: bool,
+
+    /// Run in the background as a detached session: supi keeps supervising with its
+    /// output captured, `supi attach` brings it back to a terminal
+    #[arg(long)]
+    pub detach: bool,
+
+    /// Config profile to use: a [profile.<NAME>] table in supi.toml or the user config
+    #[arg(long, value_name = "NAME", global = true)]
+    pub profile: Option<String>,
+
+    /// Name of the detached session (default: the command's file name)
+    #[arg(long, value_name = "NAME", requires = "detach")]
+    pub name: Option<String>,
+
     /// Command to run
-    #[arg(required = true)]
-    pub command: String,
+    #[arg(required = true, value_hint = ValueHint::CommandName)]
+    pub command: Option<String>,
 
     /// Arguments to pass to the command
-    #[arg(trailing_var_arg = true)]
+    #[arg(trailing_var_arg = true, value_hint = ValueHint::CommandWithArguments)]
     pub args: Vec<String>,
 }
+
+/// Prefix of the environment variables that set options, e.g. `SUPI_LOG_COLOR`
+const ENV_PREFIX: &str = "SUPI_";
+
+/// Environment variable for an option, e.g. `SUPI_RESTART_SIGNAL` for `--restart-signal`
+pub fn env_var(long: &str) -> String {
+    format!("{}{}", ENV_PREFIX, long.to_uppercase().replace('-', "_"))
+}
+
+/// The `Cli` command with a `SUPI_*` environment variable for every option.
+/// Flags accept 1/0, yes/no, on/off and true/false there. Values are hidden in
+/// `--help`, `--env` may carry secrets.
+pub fn command() -> Command {
+    Cli::command().mut_args(|arg| {
+        let Some(long) = arg.get_long() else {
+            return arg;
+        };
+        let var = env_var(long);
+        let arg = arg.env(var).hide_env_values(true);
+        match arg.get_action() {
+            ArgAction::SetTrue => arg.value_parser(BoolishValueParser::new()),
+            _ => arg,
+        }
+    })
+}
+
+/// The flag for a `SupervisorBuilder` setting, e.g. `--forward-signal` for
+/// `forward_signals`. Options are named after the setting they map to.
+pub fn flag(setting: &str) -> String {
+    Cli::command()
+        .get_arguments()
+        .find(|arg| arg.get_id() == setting)
+        .and_then(|arg| arg.get_long())
+        .map_or_else(|| setting.to_string(), |long| format!("--{}", long))
+}
+
+/// Commands for managing supi itself. A command with one of these names is still
+/// supervised when it has arguments the subcommand doesn't take (`supi man ls`);
+/// put it after `--` to always supervise it, e.g. `supi -- man`.
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Attach this terminal to a detached session: stream its output live and use
+    /// its hotkeys. Press Ctrl+] to detach again
+    Attach {
+        /// Session to attach to (default: the only running session)
+        #[arg(add = ArgValueCandidates::new(completions::sessions))]
+        name: Option<String>,
+    },
+
+    /// Disconnect all terminals attached to a detached session, leaving it running
+    Detach {
+        /// Session to detach (default: the only running session)
+        #[arg(add = ArgValueCandidates::new(completions::sessions))]
+        name: Option<String>,
+    },
+
+    /// Print a shell completion script. Completions include signal and color names
+    /// and running session names. Example: supi completions bash > ~/.local/share/bash-completion/completions/supi
+    Completions {
+        /// Shell to print the script for
+        shell: Shell,
+    },
+
+    /// Print the man page (roff). Example: supi man | man -l -
+    Man,
+
+    /// Validate supi.toml and the user config and print the settings a plain `supi`
+    /// would run with here, and where each one comes from
+    Check,
+}
@@ -0,0 +1,138 @@
+use crate::cli;
+use crate::session::Registry;
+use anyhow::Result;
+use clap::ValueEnum;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::{Bash, Elvish, EnvCompleter, Fish, Zsh};
+use std::ffi::OsStr;
+use supi_cli::{signal_names, Action, LogColor, HIGHLIGHT_PRESETS};
+
+/// Variable the completion scripts set when they call supi back for candidates
+const COMPLETE_ENV: &str = "SUPI_COMPLETE";
+
+/// Shells `supi completions` can write a script for
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Elvish,
+}
+
+/// Answer a completion request from one of the scripts and exit, if this is one.
+/// Must run before anything is printed
+pub fn handle_request() {
+    clap_complete::CompleteEnv::with_factory(cli::command)
+        .var(COMPLETE_ENV)
+        .complete();
+}
+
+/// `supi completions <shell>`: print the script that registers supi's completions.
+/// The script asks `supi` for candidates, so they follow the installed version and
+/// include running session names
+pub fn print_completions(shell: Shell) -> Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &Bash,
+        Shell::Zsh => &Zsh,
+        Shell::Fish => &Fish,
+        Shell::Elvish => &Elvish,
+    };
+    completer.write_registration(COMPLETE_ENV, "supi", "supi", "supi", &mut std::io::stdout())?;
+    Ok(())
+}
+
+/// `supi man`: print the man page in roff format
+pub fn print_man() -> Result<()> {
+    clap_mangen::Man::new(cli::command()).render(&mut std::io::stdout())?;
+    Ok(())
+}
+
+/// Catchable signal names, for `--restart-signal`, `--forward-signal` and `--dump-signal`
+pub fn signals() -> Vec<CompletionCandidate> {
+    signal_names()
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Color names, for `--log-color` and `--info-color`
+pub fn colors() -> Vec<CompletionCandidate> {
+    LogColor::NAMES.map(CompletionCandidate::new).into()
+}
+
+/// `--highlight-preset` names
+pub fn highlight_presets() -> Vec<CompletionCandidate> {
+    HIGHLIGHT_PRESETS.map(CompletionCandidate::new).into()
+}
+
+/// Names of the running detached sessions, for `supi attach` and `supi detach`
+pub fn sessions() -> Vec<CompletionCandidate> {
+    // Completion only reads, a missing registry means no sessions
+    let Some(registry) = Registry::existing() else {
+        return Vec::new();
+    };
+    registry
+        .running()
+        .into_iter()
+        .map(|session| {
+            CompletionCandidate::new(session.name).help(Some(format!("PID {}", session.pid).into()))
+        })
+        .collect()
+}
+
+/// `--on-signal <SIGNAL>[,<SIGNAL>...]=<ACTION>`: signal names, then actions after `=`
+pub fn signal_mappings(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    if current.contains('=') {
+        // The palette can't be opened by a signal
+        let actions = ["terminate", "forward", "ignore"].into_iter().chain(
+            Action::ALL
+                .into_iter()
+                .filter(|action| *action != Action::Palette)
+                .map(|action| action.name()),
+        );
+        return after_last(&current, '=', actions);
+    }
+    after_last(&current, ',', signal_names())
+}
+
+/// `--bind <KEY>=<ACTION>`: actions after `=`
+pub fn bindings(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    if !current.contains('=') {
+        return Vec::new();
+    }
+    let actions = Action::ALL
+        .map(|action| action.name())
+        .into_iter()
+        .chain(["none"]);
+    after_last(&current, '=', actions)
+}
+
+/// `--highlight <REGEX>=<COLOR>`: colors after the last `=`
+pub fn highlights(current: &OsStr) -> Vec<CompletionCandidate> {
+    let current = current.to_string_lossy();
+    if !current.contains('=') {
+        return Vec::new();
+    }
+    after_last(&current, '=', LogColor::NAMES)
+}
+
+/// Complete the part of `current` after the last `separator` from `values`,
+/// keeping what comes before it
+fn after_last<S: AsRef<str>>(
+    current: &str,
+    separator: char,
+    values: impl IntoIterator<Item = S>,
+) -> Vec<CompletionCandidate> {
+    let (prefix, partial) = match current.rfind(separator) {
+        Some(pos) => current.split_at(pos + 1),
+        None => ("", current),
+    };
+    let partial = partial.to_uppercase();
+    values
+        .into_iter()
+        .filter(|value| value.as_ref().to_uppercase().starts_with(&partial))
+        .map(|value| CompletionCandidate::new(format!("{}{}", prefix, value.as_ref())))
+        .collect()
+}
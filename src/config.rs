@@ -0,0 +1,516 @@
+use crate::cli::{self, Cli};
+use anyhow::{Context, Result};
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command, FromArgMatches};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use supi_cli::output::print_line;
+
+/// Project config file, looked up from the current directory upwards
+const PROJECT_FILE: &str = "supi.toml";
+
+/// Options that can't be set from a config file
+const CLI_ONLY: [&str; 3] = ["help", "version", "profile"];
+
+/// Options whose values may carry secrets, shown as `KEY=***`
+const SECRET_VALUES: [&str; 1] = ["envs"];
+
+/// Where the options supi runs with came from: which config files were found and
+/// which file (and profile) each option was taken from
+pub struct Settings {
+    command: Command,
+    matches: ArgMatches,
+    from_config: HashMap<String, String>,
+    files: Vec<PathBuf>,
+    profile: Option<String>,
+}
+
+/// Command line arguments for one option, by clap arg id
+type OptionArgs = (String, Vec<OsString>);
+
+/// The options of one config file, or of one profile in it
+struct Layer {
+    label: String,
+    table: toml::Table,
+}
+
+/// Parse the command line and merge in `supi.toml` and the user config.
+/// Precedence: command line, then `SUPI_*` variables, then the selected profile
+/// (project over user config), then the top-level tables (project over user
+/// config), then defaults.
+/// The subcommands that manage supi itself (attach, completions, ...) skip the config.
+pub fn load() -> Result<(Cli, Settings)> {
+    let argv = child_command_or_subcommand(std::env::args_os().collect());
+    // The command may come from the config, so it isn't required yet
+    let cli_matches = lenient_command()
+        .try_get_matches_from(&argv)
+        .unwrap_or_else(|e| e.exit());
+
+    let checking = cli_matches.subcommand_name() == Some("check");
+    if cli_matches.subcommand_name().is_some() && !checking {
+        let cli = Cli::from_arg_matches(&cli_matches).unwrap_or_else(|e| e.exit());
+        let settings = Settings {
+            command: lenient_command(),
+            matches: cli_matches,
+            from_config: HashMap::new(),
+            files: Vec::new(),
+            profile: None,
+        };
+        return Ok((cli, settings));
+    }
+
+    let profile = cli_matches
+        .subcommand_matches("check")
+        .and_then(|check| check.get_one::<String>("profile"))
+        .or_else(|| cli_matches.get_one::<String>("profile"))
+        .cloned();
+    let files = discover();
+    let layers = read_layers(&files, profile.as_deref())?;
+
+    // Later layers win, options given on the command line or in SUPI_* variables
+    // win over all of them
+    let cmd = lenient_command();
+    let mut options: BTreeMap<String, (String, Vec<OsString>)> = BTreeMap::new();
+    let mut command: Option<(String, Vec<OsString>)> = None;
+    for layer in &layers {
+        let (layer_options, layer_command) = layer.args(&cmd)?;
+        for (id, args) in layer_options {
+            options.insert(id, (layer.label.clone(), args));
+        }
+        if let Some(args) = layer_command {
+            command = Some((layer.label.clone(), args));
+        }
+    }
+
+    let mut merged = vec![argv[0].clone()];
+    let mut from_config = HashMap::new();
+    for (id, (label, args)) in options {
+        if matches!(
+            cli_matches.value_source(&id),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+        merged.extend(args);
+        from_config.insert(id, label);
+    }
+    // `supi check` shows what a plain `supi` would run with here
+    if !checking {
+        merged.extend(argv[1..].iter().cloned());
+    }
+    if let Some((label, args)) = command {
+        if checking || !cli_matches.contains_id("command") {
+            merged.push("--".into());
+            merged.extend(args);
+            from_config.insert("command".to_string(), label);
+        }
+    }
+
+    let command = if checking {
+        lenient_command()
+    } else {
+        cli::command()
+    };
+    let matches = command
+        .try_get_matches_from(merged)
+        .unwrap_or_else(|e| e.exit());
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if checking {
+        cli.subcommand = Cli::from_arg_matches(&cli_matches)
+            .unwrap_or_else(|e| e.exit())
+            .subcommand;
+    }
+
+    let settings = Settings {
+        command: lenient_command(),
+        matches,
+        from_config,
+        files,
+        profile,
+    };
+    Ok((cli, settings))
+}
+
+/// `supi man ls` supervises `man ls` as it did before supi had subcommands: a
+/// subcommand name followed by arguments that subcommand doesn't take starts the
+/// child command, as if it came after `--`
+fn child_command_or_subcommand(argv: Vec<OsString>) -> Vec<OsString> {
+    let cmd = lenient_command();
+    match cmd.clone().try_get_matches_from(&argv) {
+        Err(e) if e.kind() == ErrorKind::UnknownArgument => {}
+        _ => return argv,
+    }
+
+    for (i, arg) in argv.iter().enumerate().skip(1) {
+        if arg == "--" {
+            break;
+        }
+        if cmd.find_subcommand(arg).is_none() {
+            continue;
+        }
+        let mut args = argv.clone();
+        args.insert(i, "--".into());
+        if cmd.clone().try_get_matches_from(&args).is_ok() {
+            return args;
+        }
+    }
+    argv
+}
+
+/// `Cli` without the required command, for parsing before the config is read
+fn lenient_command() -> Command {
+    // `mut_arg` would move the positional behind the trailing args
+    cli::command().mut_args(|arg| match arg.get_id().as_str() {
+        "command" => arg.required(false),
+        _ => arg,
+    })
+}
+
+/// Config files in the order they apply: the user config, then `supi.toml`
+fn discover() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(user) = user_config().filter(|path| path.is_file()) {
+        files.push(user);
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    if let Some(project) = cwd
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+    {
+        files.push(project);
+    }
+    files
+}
+
+/// `$XDG_CONFIG_HOME/supi/config.toml`, or `~/.config/supi/config.toml`
+fn user_config() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("supi").join("config.toml"))
+}
+
+/// Top-level tables of all files first, then the selected profile of each file
+fn read_layers(files: &[PathBuf], profile: Option<&str>) -> Result<Vec<Layer>> {
+    let mut bases = Vec::new();
+    let mut profiles = Vec::new();
+    let mut available = Vec::new();
+
+    for path in files {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let mut table = text
+            .parse::<toml::Table>()
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+
+        let mut file_profiles = match table.remove("profile") {
+            Some(toml::Value::Table(file_profiles)) => file_profiles,
+            Some(_) => anyhow::bail!(
+                "Invalid config file {}: 'profile' must be a table of [profile.<name>] tables",
+                path.display()
+            ),
+            None => toml::Table::new(),
+        };
+        available.extend(file_profiles.keys().cloned());
+
+        if let Some(name) = profile {
+            match file_profiles.remove(name) {
+                Some(toml::Value::Table(table)) => profiles.push(Layer {
+                    label: format!("profile '{}' in {}", name, path.display()),
+                    table,
+                }),
+                Some(_) => anyhow::bail!(
+                    "Invalid config file {}: [profile.{}] must be a table",
+                    path.display(),
+                    name
+                ),
+                None => {}
+            }
+        }
+        bases.push(Layer {
+            label: path.display().to_string(),
+            table,
+        });
+    }
+
+    if let Some(name) = profile {
+        if profiles.is_empty() {
+            available.sort();
+            available.dedup();
+            anyhow::bail!(
+                "Profile '{}' not found, define it as [profile.{}] in {}{}",
+                name,
+                name,
+                PROJECT_FILE,
+                if available.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (available: {})", available.join(", "))
+                }
+            );
+        }
+    }
+
+    bases.extend(profiles);
+    Ok(bases)
+}
+
+impl Layer {
+    /// Command line arguments for the options in this layer, by clap arg id, and the
+    /// command if the layer sets one. Checked on their own, so errors name the file.
+    fn args(&self, cmd: &Command) -> Result<(Vec<OptionArgs>, Option<Vec<OsString>>)> {
+        let mut options = Vec::new();
+        let mut command = None;
+
+        for (key, value) in &self.table {
+            if key == "command" {
+                let args = match value {
+                    toml::Value::String(command) => vec![command.into()],
+                    toml::Value::Array(_) => values(value)
+                        .map(|args| args.into_iter().map(OsString::from).collect())
+                        .with_context(|| {
+                            format!("Invalid 'command' in {}: expected strings", self.label)
+                        })?,
+                    _ => anyhow::bail!(
+                        "Invalid 'command' in {}: expected a string or an array like [\"npm\", \"run\", \"dev\"]",
+                        self.label
+                    ),
+                };
+                command = Some(args);
+                continue;
+            }
+
+            let long = key.replace('_', "-");
+            let arg = cmd
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(long.as_str()))
+                .filter(|_| !CLI_ONLY.contains(&long.as_str()))
+                .with_context(|| format!("Unknown option '{}' in {}", key, self.label))?;
+
+            let args = if arg.get_action().takes_values() {
+                values(value)
+                    .with_context(|| {
+                        format!(
+                            "Invalid '{}' in {}: expected a string, number, boolean or an array of them",
+                            key, self.label
+                        )
+                    })?
+                    .into_iter()
+                    .map(|value| format!("--{}={}", long, value).into())
+                    .collect()
+            } else {
+                match value {
+                    toml::Value::Boolean(true) => vec![format!("--{}", long).into()],
+                    toml::Value::Boolean(false) => Vec::new(),
+                    _ => anyhow::bail!(
+                        "Invalid '{}' in {}: expected true or false",
+                        key,
+                        self.label
+                    ),
+                }
+            };
+            options.push((arg.get_id().to_string(), args));
+        }
+
+        let mut argv: Vec<OsString> = vec!["supi".into()];
+        argv.extend(options.iter().flat_map(|(_, args)| args.iter().cloned()));
+        if let Err(e) = cmd.clone().try_get_matches_from(argv) {
+            anyhow::bail!("Invalid option in {}: {}", self.label, clap_message(&e));
+        }
+
+        Ok((options, command))
+    }
+}
+
+/// A scalar or an array of scalars as strings
+fn values(value: &toml::Value) -> Option<Vec<String>> {
+    match value {
+        toml::Value::String(s) => Some(vec![s.clone()]),
+        toml::Value::Integer(n) => Some(vec![n.to_string()]),
+        toml::Value::Float(n) => Some(vec![n.to_string()]),
+        toml::Value::Boolean(b) => Some(vec![b.to_string()]),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                toml::Value::Array(_) => None,
+                item => values(item).and_then(|mut v| v.pop()),
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// First line of a clap error, without the `error: ` prefix
+fn clap_message(e: &clap::Error) -> String {
+    let rendered = e.render().to_string();
+    let line = rendered.lines().next().unwrap_or_default();
+    line.strip_prefix("error: ").unwrap_or(line).to_string()
+}
+
+impl Settings {
+    /// Found config files, with the selected profile, e.g. for the startup banner
+    pub fn describe(&self) -> Option<String> {
+        if self.files.is_empty() {
+            return None;
+        }
+        let files: Vec<String> = self
+            .files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        Some(match &self.profile {
+            Some(profile) => format!("{} (profile '{}')", files.join(", "), profile),
+            None => files.join(", "),
+        })
+    }
+
+    /// Where an option's value came from: a config file, the command line, a `SUPI_*`
+    /// variable or the default
+    pub fn source(&self, id: &str) -> Option<String> {
+        if let Some(label) = self.from_config.get(id) {
+            return Some(label.clone());
+        }
+        match self.matches.value_source(id)? {
+            ValueSource::CommandLine => Some("command line".to_string()),
+            ValueSource::EnvVariable => Some(format!(
+                "env {}",
+                self.arg(id)
+                    .and_then(|arg| arg.get_env())
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            )),
+            _ => Some("default".to_string()),
+        }
+    }
+
+    /// `value` followed by where it came from, e.g. `SIGHUP (env SUPI_RESTART_SIGNAL)`
+    pub fn with_source(&self, id: &str, value: impl std::fmt::Display) -> String {
+        match self.source(id) {
+            Some(source) => format!("{} ({})", value, source),
+            None => value.to_string(),
+        }
+    }
+
+    /// Options taken from a config file or a `SUPI_*` variable, except `skip`,
+    /// e.g. `--log-color=none (env SUPI_LOG_COLOR)`
+    pub fn describe_overrides(&self, skip: &[&str]) -> Option<String> {
+        let overrides: Vec<String> = self
+            .command
+            .get_arguments()
+            .map(|arg| arg.get_id().as_str())
+            .filter(|id| !skip.contains(id) && *id != "args")
+            .filter(|id| {
+                self.from_config.contains_key(*id)
+                    || self.matches.value_source(id) == Some(ValueSource::EnvVariable)
+            })
+            .map(|id| {
+                let (name, value) = self.shown_value(id);
+                self.with_source(id, format!("{}={}", name, value))
+            })
+            .collect();
+        (!overrides.is_empty()).then(|| overrides.join(", "))
+    }
+
+    fn arg(&self, id: &str) -> Option<&clap::Arg> {
+        self.command
+            .get_arguments()
+            .find(|arg| arg.get_id().as_str() == id)
+    }
+
+    /// Display name and value of an option, e.g. `--hide` and `a, b`, or `command`
+    /// and the command with its arguments
+    fn value(&self, id: &str) -> (String, String) {
+        let raw = |id: &str| -> Vec<String> {
+            self.matches
+                .get_raw(id)
+                .map(|values| {
+                    values
+                        .map(|value| value.to_string_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let Some(arg) = self.arg(id) else {
+            return (id.to_string(), raw(id).join(","));
+        };
+        match arg.get_long() {
+            // Flags show the parsed value, SUPI_* variables may say 1 or yes
+            Some(long) if matches!(arg.get_action(), clap::ArgAction::SetTrue) => {
+                (format!("--{}", long), self.matches.get_flag(id).to_string())
+            }
+            Some(long) => (format!("--{}", long), raw(id).join(",")),
+            None => {
+                let mut command = raw(id);
+                command.extend(raw("args"));
+                (id.to_string(), command.join(" "))
+            }
+        }
+    }
+
+    /// [`Settings::value`] for logs, with `KEY=VALUE` values of [`SECRET_VALUES`]
+    /// options reduced to `KEY=***`
+    fn shown_value(&self, id: &str) -> (String, String) {
+        let (name, value) = self.value(id);
+        if !SECRET_VALUES.contains(&id) {
+            return (name, value);
+        }
+        let keys: Vec<String> = self
+            .matches
+            .get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|spec| {
+                let spec = spec.to_string_lossy();
+                let key = spec.split_once('=').map_or(&*spec, |(key, _)| key);
+                format!("{}=***", key)
+            })
+            .collect();
+        (name, keys.join(","))
+    }
+
+    /// `supi check`: print the config files and every option that has a value
+    pub fn print(&self) {
+        match self.describe() {
+            Some(files) => print_line(&format!("[supi] Config files: {}", files)),
+            None => print_line(&format!(
+                "[supi] Config files: none, looked for {} from the current directory upwards and {}",
+                PROJECT_FILE,
+                user_config()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_else(|| "the user config".to_string())
+            )),
+        }
+
+        let mut rows = Vec::new();
+        for arg in self.command.get_arguments() {
+            let id = arg.get_id().as_str();
+            if CLI_ONLY.contains(&id) || id == "args" || self.matches.get_raw(id).is_none() {
+                continue;
+            }
+            let (name, value) = self.shown_value(id);
+            rows.push((name, value, self.source(id).unwrap_or_default()));
+        }
+
+        print_line("[supi] Resolved settings:");
+        let name_width = rows
+            .iter()
+            .map(|(name, _, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        let value_width = rows
+            .iter()
+            .map(|(_, value, _)| value.len())
+            .max()
+            .unwrap_or(0);
+        for (name, value, source) in rows {
+            print_line(&format!(
+                "  {:name_width$}  {:value_width$}  ({})",
+                name, value, source
+            ));
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crate::error::Error;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How long the env command may run before the start is given up
+const ENV_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where the child's environment comes from. Variables are resolved again before
+/// every start, so edits to env files (or to what the env command prints) are
+/// picked up on restart. Later sources win: env command, env files in order, `--env`.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    vars: Vec<(String, String)>,
+    files: Vec<PathBuf>,
+    command: Option<String>,
+    clear: bool,
+    keep: Vec<String>,
+}
+
+impl Environment {
+    /// `vars` are `KEY=VALUE` specs from `--env`, `keep` the `--keep-env` allow-list
+    /// used with `--clear-env` (entries ending in `*` match a prefix)
+    pub fn new(
+        vars: &[String],
+        files: Vec<PathBuf>,
+        command: Option<String>,
+        clear: bool,
+        keep: Vec<String>,
+    ) -> crate::Result<Self> {
+        let vars = vars
+            .iter()
+            .map(|spec| {
+                let (key, value) = spec
+                    .split_once('=')
+                    .context("expected KEY=VALUE")
+                    .and_then(|(key, value)| validate_key(key).map(|_| (key, value)))
+                    .map_err(|e| Error::invalid_option("envs", spec, e))?;
+                Ok((key.to_string(), value.to_string()))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            vars,
+            files,
+            command,
+            clear,
+            keep,
+        })
+    }
+
+    /// Whether the inherited environment is dropped (except for `--keep-env`)
+    pub fn is_cleared(&self) -> bool {
+        self.clear
+    }
+
+    /// Whether an inherited variable is passed on to the child
+    pub fn inherits(&self, key: &str) -> bool {
+        !self.clear
+            || self
+                .keep
+                .iter()
+                .any(|pattern| match pattern.strip_suffix('*') {
+                    Some(prefix) => key.starts_with(prefix),
+                    None => pattern == key,
+                })
+    }
+
+    /// Short summary of the configured sources for the startup log, if any
+    pub fn describe(&self) -> Option<String> {
+        let mut sources = Vec::new();
+        if self.clear {
+            match self.keep.is_empty() {
+                true => sources.push("cleared".to_string()),
+                false => sources.push(format!("cleared, keeping {}", self.keep.join(","))),
+            }
+        }
+        if let Some(command) = &self.command {
+            sources.push(format!("command '{}'", command));
+        }
+        for file in &self.files {
+            sources.push(file.display().to_string());
+        }
+        if !self.vars.is_empty() {
+            sources.push(format!("{} --env", self.vars.len()));
+        }
+        (!sources.is_empty()).then(|| sources.join(", "))
+    }
+
+    /// Run the env command and read the env files, returning the variables to set
+    /// on top of the (possibly cleared) inherited environment
+    pub async fn resolve(&self) -> Result<BTreeMap<String, String>> {
+        let mut resolved = BTreeMap::new();
+
+        if let Some(command) = &self.command {
+            resolved.extend(run_env_command(command).await?);
+        }
+        for file in &self.files {
+            let content = std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read env file {}", file.display()))?;
+            parse_dotenv(&content, &mut resolved, |key| self.inherited(key))
+                .with_context(|| format!("Invalid env file {}", file.display()))?;
+        }
+        resolved.extend(self.vars.iter().cloned());
+
+        Ok(resolved)
+    }
+
+    fn inherited(&self, key: &str) -> Option<String> {
+        self.inherits(key)
+            .then(|| std::env::var(key).ok())
+            .flatten()
+    }
+}
+
+fn validate_key(key: &str) -> Result<()> {
+    let mut chars = key.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        anyhow::bail!("'{}' is not a valid variable name", key);
+    }
+    Ok(())
+}
+
+/// Run a command through `sh -c` and read `KEY=VALUE` lines from its output, e.g.
+/// `direnv exec . env` or `nix develop --command env`. Lines without `=` continue
+/// the previous value. The command is killed if it takes longer than
+/// [`ENV_COMMAND_TIMEOUT`].
+async fn run_env_command(command: &str) -> Result<Vec<(String, String)>> {
+    let output = tokio::process::Command::new("sh")
+        .args(["-c", command])
+        .stderr(std::process::Stdio::inherit())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(ENV_COMMAND_TIMEOUT, output)
+        .await
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "Env command '{}' timed out after {}s",
+                command,
+                ENV_COMMAND_TIMEOUT.as_secs()
+            )
+        })?
+        .with_context(|| format!("Failed to run env command '{}'", command))?;
+    if !output.status.success() {
+        anyhow::bail!("Env command '{}' failed with {}", command, output.status);
+    }
+
+    let mut vars: Vec<(String, String)> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        match line.split_once('=') {
+            Some((key, value)) if validate_key(key).is_ok() => {
+                vars.push((key.to_string(), value.to_string()))
+            }
+            _ => {
+                if let Some((_, value)) = vars.last_mut() {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            }
+        }
+    }
+    Ok(vars)
+}
+
+/// Parse dotenv syntax into `vars`: `KEY=VALUE` lines, optional `export`, `#` comments,
+/// single quotes (literal), double quotes (escapes and expansion) and `$VAR`, `${VAR}`
+/// and `${VAR:-default}` expansion. Variables are looked up in `vars` first, then with
+/// `inherited`.
+fn parse_dotenv(
+    content: &str,
+    vars: &mut BTreeMap<String, String>,
+    inherited: impl Fn(&str) -> Option<String>,
+) -> Result<()> {
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+
+        let parsed = line
+            .split_once('=')
+            .context("expected KEY=VALUE")
+            .and_then(|(key, value)| {
+                let key = key.trim();
+                validate_key(key)?;
+                let lookup = |name: &str| vars.get(name).cloned().or_else(|| inherited(name));
+                Ok((key.to_string(), parse_value(value.trim(), &lookup)?))
+            })
+            .with_context(|| format!("line {}", index + 1))?;
+        vars.insert(parsed.0, parsed.1);
+    }
+    Ok(())
+}
+
+fn parse_value(raw: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String> {
+    if let Some(rest) = raw.strip_prefix('\'') {
+        let (value, _) = rest
+            .split_once('\'')
+            .context("missing closing single quote")?;
+        return Ok(value.to_string());
+    }
+
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        loop {
+            match chars.next().context("missing closing double quote")? {
+                '"' => return Ok(value),
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(c) => value.push(c),
+                    None => anyhow::bail!("missing closing double quote"),
+                },
+                '$' => expand(&mut chars, &mut value, lookup)?,
+                c => value.push(c),
+            }
+        }
+    }
+
+    // Unquoted: a ` #` starts a comment
+    let raw = raw
+        .split_once(" #")
+        .map_or(raw, |(value, _)| value)
+        .trim_end();
+    let mut value = String::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.extend(chars.next()),
+            '$' => expand(&mut chars, &mut value, lookup)?,
+            c => value.push(c),
+        }
+    }
+    Ok(value)
+}
+
+/// Expand the variable reference following a `$`
+fn expand(
+    chars: &mut std::str::Chars<'_>,
+    value: &mut String,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<()> {
+    if let Some(braced) = chars.as_str().strip_prefix('{') {
+        let (inner, rest) = braced.split_once('}').context("missing closing '}'")?;
+        let (name, default) = match inner.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (inner, None),
+        };
+        validate_key(name).context("invalid ${...} expansion")?;
+        match lookup(name).filter(|v| !v.is_empty()) {
+            Some(found) => value.push_str(&found),
+            None => value.push_str(default.unwrap_or_default()),
+        }
+        *chars = rest.chars();
+        return Ok(());
+    }
+
+    let rest = chars.as_str();
+    let len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    if len == 0 {
+        value.push('$');
+        return Ok(());
+    }
+    let name = &rest[..len];
+    value.push_str(&lookup(name).unwrap_or_default());
+    *chars = rest[len..].chars();
+    Ok(())
+}
+
+/// Names of variables that differ between two resolved environments, for the restart log
+pub fn changed_keys(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, String>,
+) -> Vec<String> {
+    let mut changed: Vec<String> = after
+        .iter()
+        .filter(|(key, value)| before.get(*key) != Some(value))
+        .map(|(key, _)| key.clone())
+        .collect();
+    changed.extend(
+        before
+            .keys()
+            .filter(|key| !after.contains_key(*key))
+            .map(|key| format!("-{}", key)),
+    );
+    changed
+}
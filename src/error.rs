@@ -0,0 +1,85 @@
+/// The underlying cause of an [`Error`]
+pub type Source = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors returned by the supervisor library
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A setting has an invalid value, e.g. an unknown signal name or a malformed
+    /// duration. `option` is the name of the [`SupervisorBuilder`](crate::SupervisorBuilder)
+    /// setting, e.g. `max_runtime`, and `value` the rejected value or list entry.
+    #[error("Invalid {option} '{value}'")]
+    InvalidOption {
+        option: &'static str,
+        value: String,
+        #[source]
+        source: Source,
+    },
+    /// A value couldn't be parsed on its own, e.g. an unknown action, color or hotkey
+    #[error("Invalid {kind} '{value}': {reason}")]
+    InvalidValue {
+        kind: &'static str,
+        value: String,
+        reason: String,
+    },
+    /// The command doesn't exist or couldn't be started
+    #[error("Failed to spawn child process '{command}'")]
+    Spawn {
+        command: String,
+        #[source]
+        source: Source,
+    },
+    /// Signal handling, the terminal or the reaper couldn't be set up
+    #[error("Failed to set up {what}")]
+    Setup {
+        what: &'static str,
+        #[source]
+        source: Source,
+    },
+    /// Waiting for or stopping the child process failed
+    #[error("Failed to {action} child process")]
+    Process {
+        action: &'static str,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Sending a signal to the child process group failed
+    #[error("Failed to send {} to child process group", crate::signals::signal_name(*signal))]
+    Signal {
+        signal: i32,
+        #[source]
+        source: std::io::Error,
+    },
+    /// A child process is already running
+    #[error("Process already running")]
+    AlreadyRunning,
+    /// No child process is running
+    #[error("No process running")]
+    NotRunning,
+    /// The supervisor behind a handle has stopped
+    #[error("The supervisor is not running")]
+    Stopped,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub(crate) fn invalid_option(
+        option: &'static str,
+        value: impl Into<String>,
+        source: impl Into<Source>,
+    ) -> Self {
+        Error::InvalidOption {
+            option,
+            value: value.into(),
+            source: source.into(),
+        }
+    }
+
+    pub(crate) fn setup(what: &'static str, source: impl Into<Source>) -> Self {
+        Error::Setup {
+            what,
+            source: source.into(),
+        }
+    }
+}
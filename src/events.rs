@@ -0,0 +1,115 @@
+use crate::buffer::Stream;
+use std::fmt;
+use tokio::sync::broadcast;
+use tokio::time::Duration;
+
+/// How many events a subscriber can fall behind before it misses some
+/// (its next `recv` then returns `RecvError::Lagged`)
+const EVENT_CAPACITY: usize = 1024;
+
+/// Something that happened in the supervisor or to the child, for subscribers of
+/// [`Supervisor::subscribe`](crate::Supervisor::subscribe)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SupervisorEvent {
+    /// A run of the child started
+    Spawned { run: u64, pid: u32 },
+    /// The supervisor is set up and handles signals, hotkeys and handle requests
+    Ready,
+    /// A line of child output, including lines hidden by output filters
+    Output {
+        run: u64,
+        stream: Stream,
+        line: String,
+    },
+    /// A run of the child ended. `stopped` says why supi stopped it, `None` means
+    /// it exited on its own
+    Exited {
+        run: u64,
+        exit_code: i32,
+        stopped: Option<StopReason>,
+    },
+    /// A restart was asked for, it still goes through the debounce
+    RestartRequested { source: RestartSource },
+    /// A restart request was ignored because the last restart was too recent
+    Debounced { remaining: Duration },
+    /// supi started stopping the child gracefully (SIGTERM to the process group)
+    Stopping {
+        run: u64,
+        pid: u32,
+        reason: StopReason,
+    },
+    /// supi killed the child process group with SIGKILL
+    Killed { run: u64, pid: u32 },
+}
+
+/// Why supi stopped a run of the child
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StopReason {
+    /// Stopped for good: a stop or quit action, a signal or a trigger
+    Stop,
+    /// Stopped to be started again
+    Restart,
+    /// Killed right away by the kill action
+    Kill,
+    /// The run reached `--max-runtime`
+    MaxRuntime,
+    /// The watchdog found the child over `--max-memory` or `--max-cpu`
+    Watchdog,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::Stop => write!(f, "stop"),
+            StopReason::Restart => write!(f, "restart"),
+            StopReason::Kill => write!(f, "kill"),
+            StopReason::MaxRuntime => write!(f, "max runtime"),
+            StopReason::Watchdog => write!(f, "watchdog"),
+        }
+    }
+}
+
+/// What asked for a restart
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RestartSource {
+    /// A hotkey or the command palette
+    Hotkey,
+    /// The restart signal or an `--on-signal` mapping, by signal name
+    Signal(String),
+    /// An output trigger, by its regex
+    Trigger(String),
+    /// `--restart-every` or `--restart-cron`
+    Schedule,
+    /// `SupervisorHandle::restart`
+    Request,
+}
+
+/// Sends events to every subscriber. Cheap to clone; events without subscribers
+/// are dropped.
+#[derive(Clone)]
+pub(crate) struct Events {
+    sender: broadcast::Sender<SupervisorEvent>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SupervisorEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Whether anyone listens, to skip building events that would be dropped
+    pub fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub fn emit(&self, event: SupervisorEvent) {
+        let _ = self.sender.send(event);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::buffer::Stream;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A `--hide` or `--only` pattern, optionally restricted to one stream
+#[derive(Debug, Clone)]
+pub struct FilterRule {
+    pub pattern: Regex,
+    pub stream: Option<Stream>,
+}
+
+impl FilterRule {
+    /// Parse `[stdout:|stderr:]<regex>`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (stream, pattern) = if let Some(rest) = spec.strip_prefix("stdout:") {
+            (Some(Stream::Stdout), rest)
+        } else if let Some(rest) = spec.strip_prefix("stderr:") {
+            (Some(Stream::Stderr), rest)
+        } else {
+            (None, spec)
+        };
+
+        Ok(Self {
+            pattern: Regex::new(pattern).with_context(|| format!("Invalid regex '{}'", pattern))?,
+            stream,
+        })
+    }
+
+    fn applies_to(&self, stream: Stream) -> bool {
+        self.stream.is_none_or(|s| s == stream)
+    }
+}
+
+struct FilterState {
+    hide: Vec<FilterRule>,
+    only: Vec<FilterRule>,
+    enabled: AtomicBool,
+    hidden_total: AtomicU64,
+    hidden_run: AtomicU64,
+}
+
+/// Static include/exclude filters for forwarded child output.
+/// Filtering only affects what is printed; hidden lines still reach the
+/// output buffer and triggers. Cheap to clone (shared state).
+#[derive(Clone)]
+pub struct OutputFilter {
+    state: Arc<FilterState>,
+}
+
+impl OutputFilter {
+    pub fn new(hide: Vec<FilterRule>, only: Vec<FilterRule>) -> Self {
+        Self {
+            state: Arc::new(FilterState {
+                hide,
+                only,
+                enabled: AtomicBool::new(true),
+                hidden_total: AtomicU64::new(0),
+                hidden_run: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Whether any filter rules are configured
+    pub fn has_rules(&self) -> bool {
+        !self.state.hide.is_empty() || !self.state.only.is_empty()
+    }
+
+    /// Decide whether a line should be printed, counting it if hidden
+    pub fn should_show(&self, stream: Stream, line: &str) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+
+        let hidden = self
+            .state
+            .hide
+            .iter()
+            .any(|rule| rule.applies_to(stream) && rule.pattern.is_match(line));
+
+        let mut only_rules = self
+            .state
+            .only
+            .iter()
+            .filter(|rule| rule.applies_to(stream))
+            .peekable();
+        let not_included =
+            only_rules.peek().is_some() && !only_rules.any(|rule| rule.pattern.is_match(line));
+
+        if hidden || not_included {
+            self.state.hidden_total.fetch_add(1, Ordering::Relaxed);
+            self.state.hidden_run.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.state.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Toggle filtering on or off, returning the new state
+    pub fn toggle(&self) -> bool {
+        !self.state.enabled.fetch_xor(true, Ordering::Relaxed)
+    }
+
+    /// Total number of lines hidden since supi started
+    pub fn hidden_total(&self) -> u64 {
+        self.state.hidden_total.load(Ordering::Relaxed)
+    }
+
+    /// Number of lines hidden since the last call (i.e. during the current run)
+    pub fn take_hidden_run(&self) -> u64 {
+        self.state.hidden_run.swap(0, Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,76 @@
+use crate::error::{Error, Result};
+use crate::events::{Events, SupervisorEvent};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::Duration;
+
+/// A request sent to the supervisor event loop by a [`SupervisorHandle`]
+pub(crate) enum Request {
+    Restart(oneshot::Sender<()>),
+    Stop(oneshot::Sender<()>),
+    Status(oneshot::Sender<Status>),
+}
+
+/// Controls a running [`Supervisor`](crate::Supervisor) from other tasks.
+/// Cheap to clone; requests fail with [`Error::Stopped`] once the supervisor has stopped.
+#[derive(Clone)]
+pub struct SupervisorHandle {
+    requests: mpsc::UnboundedSender<Request>,
+    events: Events,
+}
+
+impl SupervisorHandle {
+    pub(crate) fn new(requests: mpsc::UnboundedSender<Request>, events: Events) -> Self {
+        Self { requests, events }
+    }
+
+    /// Receive lifecycle events and child output from now on, see [`SupervisorEvent`]
+    pub fn subscribe(&self) -> broadcast::Receiver<SupervisorEvent> {
+        self.events.subscribe()
+    }
+
+    /// Restart the child, or start it if it isn't running. Goes through the restart
+    /// debounce like a hotkey or signal, and returns once the request was handled.
+    pub async fn restart(&self) -> Result<()> {
+        self.request(Request::Restart).await
+    }
+
+    /// Stop the child gracefully and end the supervisor, `Supervisor::run` then returns
+    pub async fn stop(&self) -> Result<()> {
+        self.request(Request::Stop).await
+    }
+
+    /// The current state of the child
+    pub async fn status(&self) -> Result<Status> {
+        self.request(Request::Status).await
+    }
+
+    async fn request<T>(&self, request: impl FnOnce(oneshot::Sender<T>) -> Request) -> Result<T> {
+        let (reply, response) = oneshot::channel();
+        self.requests
+            .send(request(reply))
+            .map_err(|_| Error::Stopped)?;
+        response.await.map_err(|_| Error::Stopped)
+    }
+}
+
+/// A snapshot of the supervised child, from [`SupervisorHandle::status`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Status {
+    /// PID of the running child process
+    pub pid: Option<u32>,
+    /// Number of the current (or most recent) run, 0 before the first start
+    pub run: u64,
+    /// Whether the child process group is paused (SIGSTOP)
+    pub paused: bool,
+    /// How long the running child has been up
+    pub uptime: Option<Duration>,
+    /// Exit code of the most recent run, 128 + signal if it was killed
+    pub last_exit_code: Option<i32>,
+}
+
+impl Status {
+    pub fn is_running(&self) -> bool {
+        self.pid.is_some()
+    }
+}
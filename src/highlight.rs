@@ -0,0 +1,103 @@
+use crate::output::LogColor;
+use anyhow::{Context, Result};
+use crossterm::style::Stylize;
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Colorize matches of a regex in forwarded child output
+#[derive(Debug, Clone)]
+pub struct HighlightRule {
+    pub pattern: Regex,
+    pub color: LogColor,
+}
+
+impl HighlightRule {
+    /// Parse a rule of the form `<regex>=<color>`.
+    /// The last `=` separates the color, so the regex itself may contain `=`.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (pattern, color) = spec.rsplit_once('=').context("expected <regex>=<color>")?;
+
+        Ok(Self {
+            pattern: Regex::new(pattern).with_context(|| format!("Invalid regex '{}'", pattern))?,
+            color: color.parse::<LogColor>()?,
+        })
+    }
+}
+
+/// Names of the built-in presets
+pub const PRESETS: [&str; 1] = ["logs"];
+
+/// Built-in rule sets for common log formats
+pub fn preset(name: &str) -> Result<Vec<HighlightRule>> {
+    let rules: &[(&str, LogColor)] = match name.to_lowercase().as_str() {
+        "logs" => &[
+            (
+                r"(?i)\b(error|err|fatal|panic|panicked|exception|failed)\b",
+                LogColor::Red,
+            ),
+            (r"(?i)\b(warn|warning|deprecated)\b", LogColor::Yellow),
+            (r"(?i)\b(info|success|ready)\b", LogColor::Green),
+            (r"(?i)\b(debug|trace)\b", LogColor::Blue),
+        ],
+        _ => anyhow::bail!("expected one of {}", PRESETS.join(", ")),
+    };
+
+    Ok(rules
+        .iter()
+        .map(|(pattern, color)| HighlightRule {
+            pattern: Regex::new(pattern).expect("preset regex is valid"),
+            color: *color,
+        })
+        .collect())
+}
+
+/// Applies highlight rules to lines of child output
+#[derive(Debug, Clone)]
+pub struct Highlighter {
+    rules: Vec<HighlightRule>,
+}
+
+impl Highlighter {
+    pub fn new(rules: Vec<HighlightRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Colorize all matched spans. Earlier rules win when spans overlap.
+    /// Lines that already contain ANSI escape sequences are left untouched.
+    pub fn apply<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        if self.rules.is_empty() || line.contains('\x1b') {
+            return Cow::Borrowed(line);
+        }
+
+        let mut spans: Vec<(usize, usize, LogColor)> = Vec::new();
+        for rule in &self.rules {
+            for m in rule.pattern.find_iter(line) {
+                let overlaps = spans
+                    .iter()
+                    .any(|(start, end, _)| m.start() < *end && *start < m.end());
+                if !m.is_empty() && !overlaps {
+                    spans.push((m.start(), m.end(), rule.color));
+                }
+            }
+        }
+
+        if spans.is_empty() {
+            return Cow::Borrowed(line);
+        }
+        spans.sort_by_key(|(start, _, _)| *start);
+
+        let mut result = String::with_capacity(line.len() + spans.len() * 16);
+        let mut pos = 0;
+        for (start, end, color) in spans {
+            result.push_str(&line[pos..start]);
+            match color.to_crossterm_color() {
+                Some(c) => result.push_str(&line[start..end].with(c).to_string()),
+                None => result.push_str(&line[start..end]),
+            }
+            pos = end;
+        }
+        result.push_str(&line[pos..]);
+
+        Cow::Owned(result)
+    }
+}
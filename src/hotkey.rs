@@ -1,21 +1,184 @@
+use crate::action::Action;
+use crate::error::Error;
+use crate::keys::{KeyChord, KeySequence};
+use crate::output::Output;
+use crate::palette::{Command, Prompt, PromptEvent};
 use anyhow::Result;
 use crossterm::{
-    event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
+    event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     terminal::{disable_raw_mode, enable_raw_mode},
 };
 use nix::sys::signal::{self, Signal};
 use nix::unistd::Pid;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio_stream::StreamExt;
 
-/// Hotkey event emitted when the configured key is pressed
-#[derive(Debug, Clone, Copy)]
-pub struct HotkeyPressed;
+/// Bindings that apply unless the key is taken by a --*-hotkey flag or --bind
+const DEFAULT_BINDINGS: [(char, Action); 8] = [
+    ('s', Action::Stop),
+    ('k', Action::Kill),
+    ('q', Action::Quit),
+    ('l', Action::Clear),
+    ('i', Action::Status),
+    ('p', Action::Pause),
+    ('?', Action::Help),
+    (':', Action::Palette),
+];
 
-/// Manages terminal input and detects hotkey presses
+/// How long a started leader sequence waits for its next key
+const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Result of looking up pressed keys in the keymap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyLookup {
+    /// The keys complete a binding
+    Action(Action),
+    /// The keys are the start of a leader sequence
+    Pending,
+    /// No binding starts with these keys
+    Unbound,
+}
+
+/// Maps hotkeys to supervisor actions
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeySequence, Action)>,
+}
+
+impl Keymap {
+    /// Build the keymap from the dedicated hotkey flags, `--bind key=action`
+    /// overrides (`key=none` unbinds) and the default bindings for free keys
+    pub fn new(
+        restart_hotkey: &KeySequence,
+        dump_hotkey: &KeySequence,
+        filter_hotkey: &KeySequence,
+        binds: &[String],
+    ) -> crate::Result<Self> {
+        let mut keymap = Self {
+            bindings: Vec::new(),
+        };
+        // The setting and value each key was bound by, latest last, for error messages
+        let mut origins: Vec<(KeySequence, &'static str, String)> = Vec::new();
+        for (option, key, action) in [
+            ("restart_hotkey", restart_hotkey, Action::Restart),
+            ("dump_hotkey", dump_hotkey, Action::Dump),
+            ("filter_hotkey", filter_hotkey, Action::ToggleFilter),
+        ] {
+            if let Some(other) = keymap.action_for(key.chords()) {
+                return Err(Error::invalid_option(
+                    option,
+                    key.to_string(),
+                    format!("the hotkey is already used for {}", other),
+                ));
+            }
+            keymap.bindings.push((key.clone(), action));
+            origins.push((key.clone(), option, key.to_string()));
+        }
+
+        let mut unbound = Vec::new();
+        for bind in binds {
+            let invalid = |source: crate::Source| Error::invalid_option("binds", bind, source);
+            let (key, action) = bind
+                .split_once('=')
+                .ok_or_else(|| invalid("expected <key>=<action>".into()))?;
+            let key: KeySequence = key.parse().map_err(|e: Error| invalid(e.into()))?;
+
+            keymap.bindings.retain(|(k, _)| *k != key);
+            if action == "none" {
+                unbound.push(key);
+            } else {
+                let action = Action::parse(action).map_err(|e| invalid(e.into()))?;
+                keymap.bindings.push((key.clone(), action));
+                origins.push((key, "binds", bind.clone()));
+            }
+        }
+
+        // A key that also starts a longer sequence could never complete it
+        for (key, action) in &keymap.bindings {
+            if let Some((longer, other)) = keymap
+                .bindings
+                .iter()
+                .find(|(k, _)| k != key && k.starts_with(key.chords()))
+            {
+                let (option, value) = origins
+                    .iter()
+                    .rev()
+                    .find(|(k, ..)| k == longer)
+                    .map_or(("binds", longer.to_string()), |(_, option, value)| {
+                        (*option, value.clone())
+                    });
+                return Err(Error::invalid_option(
+                    option,
+                    value,
+                    format!(
+                        "'{}' ({}) is a prefix of '{}' ({}), unbind one of them",
+                        key, action, longer, other
+                    ),
+                ));
+            }
+        }
+
+        for (key, action) in DEFAULT_BINDINGS {
+            let key = KeySequence::from(KeyChord::char(key));
+            let conflicts = unbound.contains(&key)
+                || keymap
+                    .bindings
+                    .iter()
+                    .any(|(k, _)| k.starts_with(key.chords()) || key.starts_with(k.chords()));
+            if !conflicts {
+                keymap.bindings.push((key, action));
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    /// Action bound to exactly this key sequence, if any
+    pub fn action_for(&self, keys: &[KeyChord]) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(k, _)| k.chords() == keys)
+            .map(|(_, action)| *action)
+    }
+
+    /// Look up the keys pressed so far
+    pub fn lookup(&self, keys: &[KeyChord]) -> KeyLookup {
+        if let Some(action) = self.action_for(keys) {
+            KeyLookup::Action(action)
+        } else if self.bindings.iter().any(|(k, _)| k.starts_with(keys)) {
+            KeyLookup::Pending
+        } else {
+            KeyLookup::Unbound
+        }
+    }
+
+    /// First key bound to an action, if any
+    pub fn key_for(&self, action: Action) -> Option<&KeySequence> {
+        self.bindings
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(key, _)| key)
+    }
+
+    /// All bindings in the order they were defined
+    pub fn bindings(&self) -> &[(KeySequence, Action)] {
+        &self.bindings
+    }
+
+    /// Human-readable list of all bindings, e.g. `r=restart, s=stop`
+    pub fn describe(&self) -> String {
+        self.bindings
+            .iter()
+            .map(|(key, action)| format!("{}={}", key, action))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Manages terminal input and maps key presses to actions and palette commands
 pub struct HotkeyListener {
-    hotkey: char,
-    receiver: mpsc::UnboundedReceiver<HotkeyPressed>,
+    receiver: mpsc::UnboundedReceiver<Command>,
     _cleanup: Option<TerminalCleanup>,
 }
 
@@ -29,29 +192,23 @@ impl Drop for TerminalCleanup {
 }
 
 impl HotkeyListener {
-    /// Create a new hotkey listener that monitors for the specified key press
+    /// Create a new hotkey listener that emits the actions bound in the keymap.
+    /// The palette action opens a `:` prompt whose commands are emitted when submitted.
     /// Note: This does not enable raw mode yet. Call `enable_raw_mode()` after
     /// validating that the command exists.
-    pub fn new(hotkey: char) -> Result<Self> {
+    pub fn new(keymap: Keymap, output: Output) -> Result<Self> {
         let (sender, receiver) = mpsc::unbounded_channel();
 
         // Spawn background task to read terminal events
         tokio::spawn(async move {
             let mut event_stream = EventStream::new();
+            let mut pending: Vec<KeyChord> = Vec::new();
+            let mut last_key = Instant::now();
+            let mut prompt = Prompt::default();
+            let mut prompt_open = false;
 
             while let Some(event) = event_stream.next().await {
                 match event {
-                    Ok(Event::Key(KeyEvent {
-                        code: KeyCode::Char(c),
-                        modifiers: KeyModifiers::NONE,
-                        ..
-                    })) if c == hotkey => {
-                        // Hotkey pressed! Send event
-                        if sender.send(HotkeyPressed).is_err() {
-                            // Channel closed, exit task
-                            break;
-                        }
-                    }
                     Ok(Event::Key(KeyEvent {
                         code: KeyCode::Char('c'),
                         modifiers: KeyModifiers::CONTROL,
@@ -63,6 +220,73 @@ impl HotkeyListener {
                         let _ = signal::kill(pid, Signal::SIGINT);
                         break;
                     }
+                    Ok(Event::Key(KeyEvent {
+                        code,
+                        modifiers,
+                        kind: KeyEventKind::Press,
+                        ..
+                    })) if prompt_open => match prompt.handle_key(code, modifiers) {
+                        PromptEvent::Edited => output.set_prompt(Some(&prompt.render())),
+                        PromptEvent::Candidates(candidates) => {
+                            output.info(&format!("[supi] {}", candidates.join("  ")))
+                        }
+                        PromptEvent::Cancel => {
+                            prompt_open = false;
+                            output.set_prompt(None);
+                        }
+                        PromptEvent::Submit(line) => {
+                            prompt_open = false;
+                            output.set_prompt(None);
+                            match Command::parse(&line) {
+                                Ok(command) => {
+                                    output.log(&format!("[supi] Command: {}", line));
+                                    if sender.send(command).is_err() {
+                                        break;
+                                    }
+                                }
+                                Err(e) => output.elog(&format!("[supi] {:#}", e)),
+                            }
+                        }
+                    },
+                    Ok(Event::Key(KeyEvent {
+                        code,
+                        modifiers,
+                        kind: KeyEventKind::Press,
+                        ..
+                    })) => {
+                        // Abandon a leader sequence that was started too long ago
+                        if last_key.elapsed() > SEQUENCE_TIMEOUT {
+                            pending.clear();
+                        }
+                        last_key = Instant::now();
+                        pending.push(KeyChord::from_event(code, modifiers));
+
+                        let mut lookup = keymap.lookup(&pending);
+                        if lookup == KeyLookup::Unbound && pending.len() > 1 {
+                            // The sequence broke off, the last key may start a new one
+                            pending.drain(..pending.len() - 1);
+                            lookup = keymap.lookup(&pending);
+                        }
+
+                        match lookup {
+                            KeyLookup::Action(Action::Palette) => {
+                                pending.clear();
+                                prompt_open = true;
+                                prompt.open();
+                                output.set_prompt(Some(&prompt.render()));
+                            }
+                            KeyLookup::Action(action) => {
+                                pending.clear();
+                                // Hotkey pressed! Send the bound action
+                                if sender.send(Command::Action(action)).is_err() {
+                                    // Channel closed, exit task
+                                    break;
+                                }
+                            }
+                            KeyLookup::Pending => {}
+                            KeyLookup::Unbound => pending.clear(),
+                        }
+                    }
                     Err(_) => {
                         // Error reading events, exit task
                         break;
@@ -75,7 +299,6 @@ impl HotkeyListener {
         });
 
         Ok(Self {
-            hotkey,
             receiver,
             _cleanup: None,
         })
@@ -89,13 +312,8 @@ impl HotkeyListener {
         Ok(())
     }
 
-    /// Get the configured hotkey character
-    pub fn hotkey(&self) -> char {
-        self.hotkey
-    }
-
-    /// Wait for the next hotkey press
-    pub async fn next(&mut self) -> Option<HotkeyPressed> {
+    /// Wait for the next hotkey action or palette command
+    pub async fn next(&mut self) -> Option<Command> {
         self.receiver.recv().await
     }
 }
@@ -0,0 +1,245 @@
+use crate::error::Error;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::fmt;
+use std::str::FromStr;
+
+/// Named keys accepted in hotkey specs, in addition to single characters
+const NAMED_KEYS: [(&str, KeyCode); 17] = [
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("return", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("esc", KeyCode::Esc),
+    ("escape", KeyCode::Esc),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+];
+
+/// A single key press with modifiers, e.g. `r`, `R`, `ctrl+r`, `f5`, `alt+shift+x`.
+/// Shift is folded into the character for printable keys, so `shift+x` equals `X`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// A plain character key without modifiers
+    pub fn char(c: char) -> Self {
+        Self {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    /// Normalize a key event reported by the terminal so it can be compared
+    /// with parsed chords
+    pub fn from_event(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => Self {
+                code: KeyCode::Char(c.to_ascii_lowercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::Char(_) => Self {
+                code,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            // Some terminals report Shift+Tab as BackTab
+            KeyCode::BackTab => Self {
+                code: KeyCode::Tab,
+                modifiers: modifiers | KeyModifiers::SHIFT,
+            },
+            _ => Self { code, modifiers },
+        }
+    }
+
+    fn parse(spec: &str) -> Result<Self> {
+        // A lone character is always taken literally (this allows `+`)
+        let mut chars = spec.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Self::validate(KeyCode::Char(c), KeyModifiers::NONE);
+        }
+
+        let mut parts: Vec<&str> = spec.split('+').collect();
+        let key = parts.pop().unwrap_or_default();
+        if key.is_empty() {
+            anyhow::bail!("missing key after modifier");
+        }
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            let modifier = match part.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => anyhow::bail!("unknown modifier '{}'. Supported: ctrl, alt, shift", part),
+            };
+            if modifiers.contains(modifier) {
+                anyhow::bail!("modifier '{}' given twice", part);
+            }
+            modifiers |= modifier;
+        }
+
+        let code = Self::parse_key(key).ok_or_else(|| {
+            anyhow::anyhow!(
+                "unknown key '{}'. Use a single character, f1-f12 or one of: {}",
+                key,
+                NAMED_KEYS.map(|(name, _)| name).join(", ")
+            )
+        })?;
+
+        Self::validate(code, modifiers)
+    }
+
+    fn parse_key(key: &str) -> Option<KeyCode> {
+        let mut chars = key.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Some(KeyCode::Char(c));
+        }
+
+        let key = key.to_lowercase();
+        if let Some(n) = key.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            return (1..=12).contains(&n).then_some(KeyCode::F(n));
+        }
+        NAMED_KEYS
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, code)| *code)
+    }
+
+    /// Reject combinations that terminals cannot report reliably
+    fn validate(code: KeyCode, mut modifiers: KeyModifiers) -> Result<Self> {
+        let mut code = code;
+        if let KeyCode::Char(c) = code {
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                if !c.is_ascii_alphabetic() {
+                    anyhow::bail!("ctrl can only be combined with letters");
+                }
+                if modifiers.contains(KeyModifiers::SHIFT) {
+                    anyhow::bail!(
+                        "terminals cannot distinguish ctrl+shift+<letter> from ctrl+<letter>"
+                    );
+                }
+                let c = c.to_ascii_lowercase();
+                match c {
+                    'c' => anyhow::bail!("ctrl+c is reserved for exiting supi"),
+                    'h' | 'i' | 'm' | 'j' => anyhow::bail!(
+                        "terminals send ctrl+{} as {}, bind that key instead",
+                        c,
+                        match c {
+                            'h' => "backspace",
+                            'i' => "tab",
+                            _ => "enter",
+                        }
+                    ),
+                    _ => {}
+                }
+                code = KeyCode::Char(c);
+            } else if modifiers.contains(KeyModifiers::SHIFT) {
+                if !c.is_alphabetic() {
+                    anyhow::bail!(
+                        "shift can only be combined with letters, use the shifted character instead"
+                    );
+                }
+                code = KeyCode::Char(c.to_uppercase().next().unwrap_or(c));
+                modifiers -= KeyModifiers::SHIFT;
+            }
+        }
+
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(_, c)| *c == code)
+                    .map(|(name, _)| *name)
+                    .unwrap_or("?");
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+/// A hotkey: one chord, or a leader sequence of chords separated by spaces
+/// (e.g. `space r`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySequence(Vec<KeyChord>);
+
+impl KeySequence {
+    pub fn chords(&self) -> &[KeyChord] {
+        &self.0
+    }
+
+    /// Whether this sequence starts with (or equals) `other`
+    pub fn starts_with(&self, other: &[KeyChord]) -> bool {
+        self.0.starts_with(other)
+    }
+}
+
+impl From<KeyChord> for KeySequence {
+    fn from(chord: KeyChord) -> Self {
+        Self(vec![chord])
+    }
+}
+
+impl FromStr for KeySequence {
+    type Err = Error;
+
+    fn from_str(spec: &str) -> Result<Self, Error> {
+        // A lone space is the space key, not an empty sequence
+        if spec == " " {
+            return Ok(Self(vec![KeyChord::char(' ')]));
+        }
+
+        let invalid = |reason: String| Error::InvalidValue {
+            kind: "hotkey",
+            value: spec.to_string(),
+            reason,
+        };
+        let chords = spec
+            .split_whitespace()
+            .map(KeyChord::parse)
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| invalid(format!("{:#}", e)))?;
+        if chords.is_empty() {
+            return Err(invalid("empty key".to_string()));
+        }
+        Ok(Self(chords))
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let chords: Vec<String> = self.0.iter().map(|c| c.to_string()).collect();
+        write!(f, "{}", chords.join(" "))
+    }
+}
@@ -0,0 +1,242 @@
+use crate::error::Error;
+use crate::limits::ResourceLimits;
+use anyhow::{Context, Result};
+use nix::unistd::{Gid, Group, Uid, User};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// The user the child runs as after supi drops privileges
+#[derive(Debug, Clone)]
+struct TargetUser {
+    name: String,
+    uid: Uid,
+    gid: Gid,
+    groups: Vec<Gid>,
+    home: PathBuf,
+}
+
+/// How the child process is launched: working directory, umask, identity and
+/// resource limits
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    cwd: Option<PathBuf>,
+    umask: Option<u32>,
+    user: Option<TargetUser>,
+    limits: ResourceLimits,
+}
+
+impl LaunchOptions {
+    /// Validate `--cwd`, `--umask`, `--user` and `--group`. Switching users requires
+    /// supi to run as root; asking for the current user is allowed and a no-op.
+    pub fn new(
+        cwd: Option<PathBuf>,
+        umask: Option<&str>,
+        user: Option<&str>,
+        group: Option<&str>,
+    ) -> crate::Result<Self> {
+        if let Some(cwd) = &cwd {
+            if !cwd.is_dir() {
+                return Err(Error::invalid_option(
+                    "cwd",
+                    cwd.display().to_string(),
+                    "not a directory",
+                ));
+            }
+        }
+
+        let umask = umask
+            .map(|value| parse_umask(value).map_err(|e| Error::invalid_option("umask", value, e)))
+            .transpose()?;
+
+        let user = match (user, group) {
+            (None, None) => None,
+            (user, group) => resolve_user(user, group)?,
+        };
+
+        Ok(Self {
+            cwd,
+            umask,
+            user,
+            limits: ResourceLimits::default(),
+        })
+    }
+
+    /// Also apply resource limits, niceness and CPU affinity to the child
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Working directory of the child, when set with `--cwd`
+    pub fn cwd(&self) -> Option<&Path> {
+        self.cwd.as_deref()
+    }
+
+    /// Summary for the startup log, e.g.
+    /// `cwd=/srv/app, umask=0027, user=app (uid 1000, gid 1000, groups 1000,27)`
+    pub fn describe(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(cwd) = &self.cwd {
+            parts.push(format!("cwd={}", cwd.display()));
+        }
+        if let Some(umask) = self.umask {
+            parts.push(format!("umask={:04o}", umask));
+        }
+        if let Some(user) = &self.user {
+            parts.push(format!(
+                "user={} (uid {}, gid {}, groups {})",
+                user.name,
+                user.uid,
+                user.gid,
+                user.groups
+                    .iter()
+                    .map(|gid| gid.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// Apply the options to the child's command. Call this before setting variables
+    /// from `--env`, so those can still override `HOME`, `USER` and `LOGNAME`.
+    pub fn apply(&self, command: &mut Command) {
+        // Limits go first, raising them may need the privileges dropped below
+        self.limits.apply(command);
+
+        if let Some(cwd) = &self.cwd {
+            command.current_dir(cwd);
+        }
+
+        if let Some(user) = &self.user {
+            command
+                .env("HOME", &user.home)
+                .env("USER", &user.name)
+                .env("LOGNAME", &user.name);
+        }
+
+        if self.umask.is_none() && self.user.is_none() {
+            return;
+        }
+
+        let umask = self.umask;
+        let ids = self
+            .user
+            .as_ref()
+            .map(|user| (user.uid, user.gid, user.groups.clone()));
+
+        // SAFETY: the closure runs between fork and exec and only makes
+        // async-signal-safe calls; everything it needs is prepared beforehand
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(umask) = umask {
+                    nix::libc::umask(umask as nix::libc::mode_t);
+                }
+                // Supplementary groups and the group have to be set while still root
+                if let Some((uid, gid, groups)) = &ids {
+                    nix::unistd::setgroups(groups)?;
+                    nix::unistd::setgid(*gid)?;
+                    nix::unistd::setuid(*uid)?;
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Parse an octal umask like `022`, `0027` or `0o077`
+fn parse_umask(value: &str) -> Result<u32> {
+    let digits = value.trim_start_matches("0o");
+    let umask = u32::from_str_radix(digits, 8)
+        .ok()
+        .filter(|umask| *umask <= 0o777)
+        .context("expected an octal mode like 022")?;
+    Ok(umask)
+}
+
+fn resolve_user(user: Option<&str>, group: Option<&str>) -> crate::Result<Option<TargetUser>> {
+    // Errors are reported for the user if one was given, otherwise for the group
+    let invalid = |source: crate::Source| match (user, group) {
+        (Some(spec), _) => Error::invalid_option("user", spec, source),
+        (None, spec) => Error::invalid_option("group", spec.unwrap_or_default(), source),
+    };
+
+    let current = Uid::current();
+    let mut target = lookup_user(&user.map_or_else(|| current.to_string(), str::to_string))
+        .map_err(|e| invalid(e.into()))?;
+    if let Some(spec) = group {
+        target.gid = lookup_group(spec).map_err(|e| Error::invalid_option("group", spec, e))?;
+    }
+
+    if current == target.uid && Gid::current() == target.gid {
+        return Ok(None);
+    }
+    if !Uid::effective().is_root() {
+        return Err(invalid(
+            format!(
+                "supi needs to run as root to switch to {}:{} (running as uid {})",
+                target.name, target.gid, current
+            )
+            .into(),
+        ));
+    }
+
+    target.groups =
+        supplementary_groups(&target.name, target.gid).map_err(|e| invalid(e.into()))?;
+    Ok(Some(target))
+}
+
+/// Look up a user by name or numeric uid. Like container runtimes, a uid without a
+/// passwd entry is allowed; it gets the same gid and `/` as home.
+fn lookup_user(spec: &str) -> Result<TargetUser> {
+    let uid = spec.parse::<u32>().ok().map(Uid::from_raw);
+    let found = match uid {
+        Some(uid) => User::from_uid(uid),
+        None => User::from_name(spec),
+    }
+    .with_context(|| format!("Failed to look up user '{}'", spec))?;
+
+    match (found, uid) {
+        (Some(user), _) => Ok(TargetUser {
+            name: user.name,
+            uid: user.uid,
+            gid: user.gid,
+            groups: Vec::new(),
+            home: user.dir,
+        }),
+        (None, Some(uid)) => Ok(TargetUser {
+            name: uid.to_string(),
+            uid,
+            gid: Gid::from_raw(uid.as_raw()),
+            groups: Vec::new(),
+            home: PathBuf::from("/"),
+        }),
+        (None, None) => anyhow::bail!("no such user"),
+    }
+}
+
+/// Look up a group by name or numeric gid
+fn lookup_group(spec: &str) -> Result<Gid> {
+    if let Ok(gid) = spec.parse::<u32>() {
+        return Ok(Gid::from_raw(gid));
+    }
+    Group::from_name(spec)
+        .with_context(|| format!("Failed to look up group '{}'", spec))?
+        .map(|group| group.gid)
+        .context("no such group")
+}
+
+/// The groups the user is a member of, including `gid`
+#[cfg(not(target_vendor = "apple"))]
+fn supplementary_groups(name: &str, gid: Gid) -> Result<Vec<Gid>> {
+    let name = std::ffi::CString::new(name).context("User name contains a NUL byte")?;
+    let mut groups =
+        nix::unistd::getgrouplist(&name, gid).context("Failed to look up supplementary groups")?;
+    groups.dedup();
+    Ok(groups)
+}
+
+#[cfg(target_vendor = "apple")]
+fn supplementary_groups(_name: &str, gid: Gid) -> Result<Vec<Gid>> {
+    Ok(vec![gid])
+}
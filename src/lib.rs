@@ -0,0 +1,64 @@
+//! The process supervision behind the `supi` command, for embedding in other tools.
+//!
+//! A [`SupervisorBuilder`] takes the same settings as the `supi` options and builds
+//! a [`Supervisor`], whose event loop runs the child until it's time to exit. A
+//! [`SupervisorHandle`] restarts, stops or queries it from other tasks meanwhile.
+//!
+//! ```no_run
+//! use supi_cli::SupervisorBuilder;
+//!
+//! # async fn example() -> supi_cli::Result<()> {
+//! let mut supervisor = SupervisorBuilder::new("npm")
+//!     .args(["run", "dev"])
+//!     .restart_signal("SIGHUP")
+//!     .hotkeys(false)
+//!     .build()?;
+//!
+//! let handle = supervisor.handle();
+//! tokio::spawn(async move {
+//!     handle.restart().await?;
+//!     println!("Child running as PID {:?}", handle.status().await?.pid);
+//!     handle.stop().await
+//! });
+//!
+//! let exit_code = supervisor.run().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod action;
+mod buffer;
+mod builder;
+mod env;
+mod error;
+mod events;
+mod filter;
+mod handle;
+mod highlight;
+mod hotkey;
+mod keys;
+mod launch;
+mod limits;
+pub mod output;
+mod palette;
+mod process;
+mod reaper;
+mod schedule;
+mod signals;
+mod supervisor;
+mod triggers;
+mod units;
+mod watchdog;
+
+pub use action::Action;
+pub use buffer::Stream;
+pub use builder::SupervisorBuilder;
+pub use error::{Error, Result, Source};
+pub use events::{RestartSource, StopReason, SupervisorEvent};
+pub use handle::{Status, SupervisorHandle};
+pub use highlight::PRESETS as HIGHLIGHT_PRESETS;
+pub use keys::KeySequence;
+pub use output::{LogColor, Output};
+pub use process::ProcessManager;
+pub use signals::{signal_name, signal_names, SignalAction, SignalEvent, SignalHandler};
+pub use supervisor::Supervisor;
@@ -0,0 +1,362 @@
+use crate::error::Error;
+use crate::units::{parse_duration, parse_size};
+use anyhow::{Context, Result};
+use nix::sys::resource::{getrlimit, setrlimit, Resource, RLIM_INFINITY};
+use tokio::process::Command;
+
+/// How a limit value is written: a byte size, a CPU time or a plain count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Bytes,
+    Seconds,
+    Count,
+}
+
+/// Resource names accepted by `--limit`, as in `ulimit` and `prlimit`
+const RESOURCES: &[(&str, Resource, Unit)] = &[
+    ("as", Resource::RLIMIT_AS, Unit::Bytes),
+    ("core", Resource::RLIMIT_CORE, Unit::Bytes),
+    ("cpu", Resource::RLIMIT_CPU, Unit::Seconds),
+    ("data", Resource::RLIMIT_DATA, Unit::Bytes),
+    ("fsize", Resource::RLIMIT_FSIZE, Unit::Bytes),
+    ("memlock", Resource::RLIMIT_MEMLOCK, Unit::Bytes),
+    ("nofile", Resource::RLIMIT_NOFILE, Unit::Count),
+    ("nproc", Resource::RLIMIT_NPROC, Unit::Count),
+    ("rss", Resource::RLIMIT_RSS, Unit::Bytes),
+    ("stack", Resource::RLIMIT_STACK, Unit::Bytes),
+    #[cfg(target_os = "linux")]
+    ("locks", Resource::RLIMIT_LOCKS, Unit::Count),
+    #[cfg(target_os = "linux")]
+    ("msgqueue", Resource::RLIMIT_MSGQUEUE, Unit::Bytes),
+    #[cfg(target_os = "linux")]
+    ("sigpending", Resource::RLIMIT_SIGPENDING, Unit::Count),
+];
+
+/// One `--limit name=value` entry, applied as both soft and hard limit unless
+/// given as `soft:hard`
+#[derive(Debug, Clone)]
+struct Limit {
+    spec: String,
+    resource: Resource,
+    soft: u64,
+    hard: u64,
+}
+
+/// I/O scheduling class and priority for `--ionice`
+#[derive(Debug, Clone, Copy)]
+struct IoPriority {
+    class: u32,
+    level: u32,
+}
+
+/// Resource limits, niceness, I/O priority and CPU affinity for the child
+#[derive(Debug, Clone, Default)]
+pub struct ResourceLimits {
+    limits: Vec<Limit>,
+    nice: Option<i32>,
+    ionice: Option<(String, IoPriority)>,
+    cpu_affinity: Option<(String, Vec<usize>)>,
+}
+
+impl ResourceLimits {
+    /// Parse `--limit`, `--nice`, `--ionice` and `--cpu-affinity`
+    pub fn new(
+        limits: &[String],
+        nice: Option<i32>,
+        ionice: Option<&str>,
+        cpu_affinity: Option<&str>,
+    ) -> crate::Result<Self> {
+        let limits = limits
+            .iter()
+            .map(|spec| parse_limit(spec).map_err(|e| Error::invalid_option("limits", spec, e)))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        if let Some(nice) = nice {
+            let invalid = |reason: &str| Error::invalid_option("nice", nice.to_string(), reason);
+            if !(-20..=19).contains(&nice) {
+                return Err(invalid("must be between -20 and 19"));
+            }
+            if nice < 0 && !nix::unistd::Uid::effective().is_root() {
+                return Err(invalid("negative values need root"));
+            }
+        }
+
+        let ionice = ionice
+            .map(|spec| {
+                parse_ionice(spec)
+                    .map(|priority| (spec.to_string(), priority))
+                    .map_err(|e| Error::invalid_option("ionice", spec, e))
+            })
+            .transpose()?;
+
+        let cpu_affinity = cpu_affinity
+            .map(|spec| {
+                parse_cpu_list(spec)
+                    .map(|cpus| (spec.to_string(), cpus))
+                    .map_err(|e| Error::invalid_option("cpu_affinity", spec, e))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            limits,
+            nice,
+            ionice,
+            cpu_affinity,
+        })
+    }
+
+    /// Summary for the config banner, e.g. `nofile=4096, as=2G, nice=10, cpu-affinity=0-3`
+    pub fn describe(&self) -> Option<String> {
+        let mut parts: Vec<String> = self.limits.iter().map(|l| l.spec.clone()).collect();
+        if let Some(nice) = self.nice {
+            parts.push(format!("nice={}", nice));
+        }
+        if let Some((spec, _)) = &self.ionice {
+            parts.push(format!("ionice={}", spec));
+        }
+        if let Some((spec, _)) = &self.cpu_affinity {
+            parts.push(format!("cpu-affinity={}", spec));
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// Register a `pre_exec` hook that applies everything in the child before exec.
+    /// Must run before privileges are dropped, raising limits or lowering the nice
+    /// value needs root.
+    pub fn apply(&self, command: &mut Command) {
+        if self.describe().is_none() {
+            return;
+        }
+
+        let limits: Vec<(Resource, u64, u64)> = self
+            .limits
+            .iter()
+            .map(|limit| (limit.resource, limit.soft, limit.hard))
+            .collect();
+        let nice = self.nice;
+        let ionice = self.ionice.as_ref().map(|(_, priority)| *priority);
+        let cpu_set = self.cpu_affinity.as_ref().map(|(_, cpus)| cpu_set(cpus));
+
+        // SAFETY: the closure runs between fork and exec and only makes
+        // async-signal-safe calls; everything it needs is prepared beforehand
+        unsafe {
+            command.pre_exec(move || {
+                for (resource, soft, hard) in &limits {
+                    setrlimit(*resource, *soft, *hard)?;
+                }
+                if let Some(nice) = nice {
+                    nix::errno::Errno::result(nix::libc::setpriority(
+                        nix::libc::PRIO_PROCESS,
+                        0,
+                        nice,
+                    ))?;
+                }
+                if let Some(priority) = ionice {
+                    set_io_priority(priority)?;
+                }
+                if let Some(cpu_set) = &cpu_set {
+                    set_cpu_affinity(cpu_set)?;
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Parse `name=value` or `name=soft:hard`
+fn parse_limit(spec: &str) -> Result<Limit> {
+    let (name, value) = spec
+        .split_once('=')
+        .context("expected NAME=VALUE, e.g. nofile=4096")?;
+    let name = name.trim().to_lowercase();
+    let (resource, unit) = RESOURCES
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, resource, unit)| (*resource, *unit))
+        .with_context(|| {
+            format!(
+                "unknown resource '{}'. Supported: {}",
+                name,
+                RESOURCES
+                    .iter()
+                    .map(|(n, _, _)| *n)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+
+    let (soft, hard) = match value.split_once(':') {
+        Some((soft, hard)) => (
+            parse_limit_value(soft, unit)?,
+            parse_limit_value(hard, unit)?,
+        ),
+        None => {
+            let value = parse_limit_value(value, unit)?;
+            (value, value)
+        }
+    };
+    if soft > hard {
+        anyhow::bail!("the soft limit is above the hard limit");
+    }
+
+    // Only root can raise a hard limit
+    let (_, current_hard) = getrlimit(resource).context("Failed to read the current limit")?;
+    if hard > current_hard && !nix::unistd::Uid::effective().is_root() {
+        anyhow::bail!(
+            "raising the hard limit above {} needs root",
+            display_limit(current_hard)
+        );
+    }
+
+    Ok(Limit {
+        spec: format!("{}={}", name, value.trim()),
+        resource,
+        soft,
+        hard,
+    })
+}
+
+fn parse_limit_value(value: &str, unit: Unit) -> Result<u64> {
+    let value = value.trim();
+    if matches!(value, "unlimited" | "infinity") {
+        return Ok(RLIM_INFINITY);
+    }
+    match unit {
+        Unit::Bytes => parse_size(value),
+        Unit::Seconds => Ok(parse_duration(value)?.as_secs()),
+        Unit::Count => value
+            .parse()
+            .with_context(|| format!("'{}' is not a number", value)),
+    }
+}
+
+fn display_limit(value: u64) -> String {
+    match value {
+        RLIM_INFINITY => "unlimited".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Parse `CLASS[:LEVEL]`, where the class is realtime, best-effort or idle (or 1-3)
+/// and the level 0 (highest) to 7
+fn parse_ionice(spec: &str) -> Result<IoPriority> {
+    let (class, level) = match spec.split_once(':') {
+        Some((class, level)) => (class, Some(level)),
+        None => (spec, None),
+    };
+    let class = match class.trim().to_lowercase().as_str() {
+        "realtime" | "rt" | "1" => 1,
+        "best-effort" | "be" | "2" => 2,
+        "idle" | "3" => 3,
+        _ => anyhow::bail!(
+            "unknown class '{}'. Supported: realtime, best-effort, idle",
+            class
+        ),
+    };
+    let level = match level {
+        Some(level) => level
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|level| *level <= 7)
+            .context("the level must be between 0 and 7")?,
+        None => 4,
+    };
+    if class == 1 && !nix::unistd::Uid::effective().is_root() {
+        anyhow::bail!("the realtime class needs root");
+    }
+    Ok(IoPriority { class, level })
+}
+
+/// Parse a CPU list like `0-3`, `1,3,5` or `0-1,4-5`
+fn parse_cpu_list(spec: &str) -> Result<Vec<usize>> {
+    // The same bound as the kernel's cpu_set_t
+    const MAX_CPUS: usize = 1024;
+
+    let mut cpus = Vec::new();
+    for part in spec.split(',') {
+        let part = part.trim();
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start, end),
+            None => (part, part),
+        };
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .with_context(|| format!("'{}' is not a CPU number", n))
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start > end {
+            anyhow::bail!("range {} is backwards", part);
+        }
+        // Checked before extending, so a huge range can't exhaust memory
+        if end >= MAX_CPUS {
+            anyhow::bail!(
+                "CPU {} is out of range, the maximum is {}",
+                end,
+                MAX_CPUS - 1
+            );
+        }
+        cpus.extend(start..=end);
+    }
+    Ok(cpus)
+}
+
+#[cfg(target_os = "linux")]
+type CpuSet = nix::sched::CpuSet;
+
+#[cfg(target_os = "linux")]
+fn cpu_set(cpus: &[usize]) -> CpuSet {
+    let mut set = CpuSet::new();
+    for cpu in cpus {
+        // Validated against the size of the set when parsing
+        let _ = set.set(*cpu);
+    }
+    set
+}
+
+#[cfg(target_os = "linux")]
+fn set_cpu_affinity(set: &CpuSet) -> std::io::Result<()> {
+    nix::sched::sched_setaffinity(nix::unistd::Pid::from_raw(0), set)?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_io_priority(priority: IoPriority) -> std::io::Result<()> {
+    const IOPRIO_WHO_PROCESS: nix::libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: u32 = 13;
+
+    // SAFETY: ioprio_set takes plain integers
+    let result = unsafe {
+        nix::libc::syscall(
+            nix::libc::SYS_ioprio_set,
+            IOPRIO_WHO_PROCESS,
+            0,
+            (priority.class << IOPRIO_CLASS_SHIFT | priority.level) as nix::libc::c_int,
+        )
+    };
+    nix::errno::Errno::result(result)?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+type CpuSet = Vec<usize>;
+
+#[cfg(not(target_os = "linux"))]
+fn cpu_set(cpus: &[usize]) -> CpuSet {
+    cpus.to_vec()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cpu_affinity(_set: &CpuSet) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "CPU affinity is only supported on Linux",
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_io_priority(_priority: IoPriority) -> std::io::Result<()> {
+    Err(std::io::Error::other(
+        "I/O priorities are only supported on Linux",
+    ))
+}
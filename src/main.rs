@@ -1,62 +1,212 @@
+mod attach;
 mod cli;
-mod hotkey;
-mod output;
-mod process;
-mod signals;
-mod supervisor;
-
-use clap::Parser;
-use cli::Cli;
-use hotkey::HotkeyListener;
-use output::{LogColor, Output};
-use process::ProcessManager;
-use signals::SignalHandler;
-use supervisor::Supervisor;
-
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let args = Cli::parse();
+mod completions;
+mod config;
+mod session;
+
+use anyhow::Context;
+use cli::{Cli, Commands};
+use supi_cli::output::{self, LogColor, Output};
+use supi_cli::SupervisorBuilder;
+
+fn main() -> anyhow::Result<()> {
+    // Taken before the runtime starts its threads, changing the environment isn't
+    // thread safe
+    let session_name = session::take_session_name();
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(session_name))
+}
+
+async fn run(session_name: Option<String>) -> anyhow::Result<()> {
+    completions::handle_request();
+    let (args, settings) = config::load()?;
+
+    match &args.subcommand {
+        Some(Commands::Completions { shell }) => return completions::print_completions(*shell),
+        Some(Commands::Man) => return completions::print_man(),
+        Some(Commands::Attach { name }) => return attach::attach(name.as_deref()).await,
+        Some(Commands::Detach { name }) => return attach::detach(name.as_deref()).await,
+        Some(Commands::Check) | None => {}
+    }
+    let checking = matches!(args.subcommand, Some(Commands::Check));
+    if checking {
+        settings.print();
+    }
+
+    // Started by `supi --detach` to host the session in the background
+    if let Some(name) = session::host_name() {
+        let code = session::run_host(name).await?;
+        std::process::exit(code);
+    }
+
+    let command = args.command.clone().context(
+        "A command to run is required: pass it after the options or set `command` in supi.toml",
+    )?;
 
     // Parse log colors and create Output instance
-    let log_color = LogColor::from_str(&args.log_color).map_err(|e| anyhow::anyhow!(e))?;
-    let info_color = LogColor::from_str(&args.info_color).map_err(|e| anyhow::anyhow!(e))?;
-    let output = Output::new(log_color, info_color, args.silent);
+    let log_color = args.log_color.parse::<LogColor>()?;
+    let info_color = args.info_color.parse::<LogColor>()?;
+
+    // `supi check` goes through the same validation without the startup banners
+    let silent = args.silent || checking;
+    let output = Output::new(log_color, info_color, silent);
 
     output.log(&format!("[supi] Supervisor PID: {}", std::process::id()));
     output.log("[supi] Starting supervisor");
+    if let Some(files) = settings.describe() {
+        output.log(&format!("[supi] Config files: {}", files));
+    }
+    if let Some(name) = &session_name {
+        output.log(&format!("[supi] Running in detached session '{}'", name));
+    }
     output.log(&format!(
-        "[supi] Config: restart_signal={}, restart_hotkey='{}', stop_on_child_exit={}, restart_debounce_ms={}",
-        args.restart_signal, args.restart_hotkey, args.stop_on_child_exit, args.restart_debounce_ms
+        "[supi] Config: restart_signal={}, restart_hotkey={}, stop_on_child_exit={}, restart_debounce_ms={}",
+        settings.with_source("restart_signal", &args.restart_signal),
+        settings.with_source("restart_hotkey", format!("'{}'", args.restart_hotkey)),
+        settings.with_source("stop_on_child_exit", args.stop_on_child_exit),
+        settings.with_source("restart_debounce_ms", args.restart_debounce_ms)
     ));
+    if let Some(overrides) = settings.describe_overrides(&[
+        "restart_signal",
+        "restart_hotkey",
+        "stop_on_child_exit",
+        "restart_debounce_ms",
+    ]) {
+        output.log(&format!(
+            "[supi] From config and environment: {}",
+            overrides
+        ));
+    }
+
+    let detach = args.detach && session_name.is_none();
+    let name = args.name.clone();
+    let mut supervisor = builder(args, command.clone())
+        .log_color(log_color)
+        .info_color(info_color)
+        .silent(silent)
+        .build()
+        .map_err(cli_error)?;
 
-    let process_manager = ProcessManager::new(args.command, args.args, output.clone());
-    let signal_handler = SignalHandler::new(&args.restart_signal)?;
-
-    // Set up hotkey listener (raw mode will be enabled in supervisor after command validation)
-    let hotkey_listener = match HotkeyListener::new(args.restart_hotkey) {
-        Ok(listener) => Some(listener),
-        Err(e) => {
-            output.elog(&format!(
-                "[supi] Warning: Could not set up hotkey listener: {}",
-                e
-            ));
-            output.elog("[supi] Continuing without hotkey support (signals still work)");
-            None
-        }
-    };
-
-    let mut supervisor = Supervisor::new(
-        process_manager,
-        signal_handler,
-        hotkey_listener,
-        args.stop_on_child_exit,
-        args.restart_signal,
-        args.restart_hotkey,
-        output,
-        args.restart_debounce_ms,
-    );
-
-    supervisor.run().await?;
+    if checking {
+        supervisor.check_command()?;
+        output::print_line_colored("[supi] Config OK", info_color);
+        return Ok(());
+    }
+
+    // Everything is validated, now the session host starts supi again in the background
+    if detach {
+        supervisor.check_command()?;
+        return session::detach(name.as_deref(), &command, &output);
+    }
+
+    let exit_code = supervisor.run().await.map_err(cli_error)?;
+
+    // Drop the supervisor first so the terminal leaves raw mode before exiting
+    drop(supervisor);
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
 
     Ok(())
 }
+
+/// Name invalid settings by their flags, e.g. `Invalid --max-runtime 'soon': ...`
+fn cli_error(e: supi_cli::Error) -> anyhow::Error {
+    match e {
+        supi_cli::Error::InvalidOption {
+            option,
+            value,
+            source,
+        } => anyhow::anyhow!(
+            "Invalid {} '{}': {:#}",
+            cli::flag(option),
+            value,
+            anyhow::Error::from_boxed(source)
+        ),
+        e => e.into(),
+    }
+}
+
+/// A supervisor builder with the options from the command line and config files
+fn builder(args: Cli, command: String) -> SupervisorBuilder {
+    let mut builder = SupervisorBuilder::new(command)
+        .args(args.args)
+        .stop_on_child_exit(args.stop_on_child_exit)
+        .subreaper(args.subreaper)
+        .restart_signal(args.restart_signal)
+        .forward_signals(args.forward_signals)
+        .on_signals(args.on_signals)
+        .restart_hotkey(args.restart_hotkey)
+        .dump_hotkey(args.dump_hotkey)
+        .filter_hotkey(args.filter_hotkey)
+        .binds(args.binds)
+        .restart_debounce_ms(args.restart_debounce_ms)
+        .buffer_lines(args.buffer_lines)
+        .buffer_mb(args.buffer_mb)
+        .dump_file(args.dump_file)
+        .crash_summary_lines(args.crash_summary_lines)
+        .highlights(args.highlights)
+        .hide(args.hide)
+        .only(args.only)
+        .triggers(args.triggers)
+        .envs(args.envs)
+        .env_files(args.env_files)
+        .clear_env(args.clear_env)
+        .keep_env(args.keep_env)
+        .limits(args.limits)
+        .max_cpu_for(args.max_cpu_for)
+        .sample_interval(args.sample_interval)
+        .autostart(!args.no_autostart);
+
+    if let Some(signal) = args.dump_signal {
+        builder = builder.dump_signal(signal);
+    }
+    if let Some(preset) = args.highlight_preset {
+        builder = builder.highlight_preset(preset);
+    }
+    if let Some(command) = args.env_command {
+        builder = builder.env_command(command);
+    }
+    if let Some(cwd) = args.cwd {
+        builder = builder.cwd(cwd);
+    }
+    if let Some(umask) = args.umask {
+        builder = builder.umask(umask);
+    }
+    if let Some(user) = args.user {
+        builder = builder.user(user);
+    }
+    if let Some(group) = args.group {
+        builder = builder.group(group);
+    }
+    if let Some(nice) = args.nice {
+        builder = builder.nice(nice);
+    }
+    if let Some(ionice) = args.ionice {
+        builder = builder.ionice(ionice);
+    }
+    if let Some(cpus) = args.cpu_affinity {
+        builder = builder.cpu_affinity(cpus);
+    }
+    if let Some(size) = args.max_memory {
+        builder = builder.max_memory(size);
+    }
+    if let Some(percent) = args.max_cpu {
+        builder = builder.max_cpu(percent);
+    }
+    if let Some(duration) = args.max_runtime {
+        builder = builder.max_runtime(duration);
+    }
+    if let Some(duration) = args.restart_every {
+        builder = builder.restart_every(duration);
+    }
+    if let Some(pattern) = args.restart_cron {
+        builder = builder.restart_cron(pattern);
+    }
+    if let Some(duration) = args.start_delay {
+        builder = builder.start_delay(duration);
+    }
+    builder
+}
@@ -1,9 +1,24 @@
-use crossterm::style::{Color, ResetColor, SetForegroundColor};
+use crate::error::Error;
+use crate::highlight::Highlighter;
+use crossterm::style::{Attribute, Color, ResetColor, SetAttribute, SetForegroundColor, Stylize};
+use std::borrow::Cow;
 use std::io::{self, Write};
-use std::sync::Mutex;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-/// Global output synchronizer to prevent jumbled terminal output
-static OUTPUT_LOCK: Mutex<()> = Mutex::new(());
+/// Global output synchronizer to prevent jumbled terminal output.
+/// Holds the sticky footer (status line and command palette prompt), so every
+/// printed line can move it out of the way and redraw it below.
+static OUTPUT_LOCK: Mutex<Footer> = Mutex::new(Footer {
+    status: None,
+    prompt: None,
+});
+
+/// Lines kept at the bottom of the terminal below all other output
+struct Footer {
+    status: Option<String>,
+    prompt: Option<String>,
+}
 
 /// Log color configuration for supervisor messages
 #[derive(Debug, Clone, Copy)]
@@ -19,26 +34,12 @@ pub enum LogColor {
 }
 
 impl LogColor {
-    /// Parse a color string from CLI argument
-    pub fn from_str(s: &str) -> Result<Self, String> {
-        match s.to_lowercase().as_str() {
-            "yellow" => Ok(LogColor::Yellow),
-            "red" => Ok(LogColor::Red),
-            "green" => Ok(LogColor::Green),
-            "blue" => Ok(LogColor::Blue),
-            "cyan" => Ok(LogColor::Cyan),
-            "magenta" => Ok(LogColor::Magenta),
-            "white" => Ok(LogColor::White),
-            "none" => Ok(LogColor::None),
-            _ => Err(format!(
-                "Invalid color '{}'. Supported: yellow, red, green, blue, cyan, magenta, white, none",
-                s
-            )),
-        }
-    }
+    pub const NAMES: [&'static str; 8] = [
+        "yellow", "red", "green", "blue", "cyan", "magenta", "white", "none",
+    ];
 
     /// Convert to crossterm Color
-    fn to_crossterm_color(self) -> Option<Color> {
+    pub fn to_crossterm_color(self) -> Option<Color> {
         match self {
             LogColor::Yellow => Some(Color::Yellow),
             LogColor::Red => Some(Color::Red),
@@ -52,13 +53,37 @@ impl LogColor {
     }
 }
 
+impl FromStr for LogColor {
+    type Err = Error;
+
+    /// Parse a color string from CLI argument
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_lowercase().as_str() {
+            "yellow" => Ok(LogColor::Yellow),
+            "red" => Ok(LogColor::Red),
+            "green" => Ok(LogColor::Green),
+            "blue" => Ok(LogColor::Blue),
+            "cyan" => Ok(LogColor::Cyan),
+            "magenta" => Ok(LogColor::Magenta),
+            "white" => Ok(LogColor::White),
+            "none" => Ok(LogColor::None),
+            _ => Err(Error::InvalidValue {
+                kind: "color",
+                value: s.to_string(),
+                reason: format!("expected one of {}", LogColor::NAMES.join(", ")),
+            }),
+        }
+    }
+}
+
 /// Stateful output manager that handles supervisor and child process output
-/// with configurable colors and silent mode
+/// with configurable colors, silent mode and child output highlighting
 #[derive(Debug, Clone)]
 pub struct Output {
     log_color: LogColor,
     info_color: LogColor,
     silent: bool,
+    highlighter: Option<Arc<Highlighter>>,
 }
 
 impl Output {
@@ -68,9 +93,16 @@ impl Output {
             log_color,
             info_color,
             silent,
+            highlighter: None,
         }
     }
 
+    /// Color forwarded child output with highlight rules
+    pub(crate) fn with_highlighter(mut self, highlighter: Highlighter) -> Self {
+        self.highlighter = Some(Arc::new(highlighter));
+        self
+    }
+
     /// Print a supervisor log message (colored with log_color)
     /// Suppressed when silent mode is enabled
     pub fn log(&self, msg: &str) {
@@ -107,20 +139,116 @@ impl Output {
         eprint_line_colored(msg, self.info_color);
     }
 
-    /// Forward child process stdout (never suppressed, never colored)
+    /// Print a highlighted banner in reverse video (colored with log_color)
+    /// Never suppressed, since banners are explicitly requested by the user
+    pub fn banner(&self, msg: &str) {
+        print_banner(msg, self.log_color);
+    }
+
+    /// Print a framed panel of lines (colored with info_color)
+    /// Never suppressed, since panels are explicitly requested by the user
+    pub fn panel(&self, title: &str, lines: &[String]) {
+        print_panel(title, lines, self.info_color);
+    }
+
+    /// Clear the terminal and move the cursor to the top left corner
+    pub fn clear_screen(&self) {
+        let footer = OUTPUT_LOCK.lock().unwrap();
+        hide_footer(&footer);
+        let mut handle = io::stdout().lock();
+        let _ = crossterm::execute!(
+            handle,
+            crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+            crossterm::cursor::MoveTo(0, 0)
+        );
+        drop(handle);
+        show_footer(&footer);
+    }
+
+    /// Show (or update) the command palette prompt on the last line, or remove it with `None`.
+    /// Never suppressed, since the prompt is explicitly opened by the user
+    pub fn set_prompt(&self, text: Option<&str>) {
+        let mut footer = OUTPUT_LOCK.lock().unwrap();
+        hide_footer(&footer);
+        footer.prompt = text.map(str::to_string);
+        show_footer(&footer);
+    }
+
+    /// Show a sticky status line (in reverse video, colored with log_color) below
+    /// all other output, or remove it with `None`. Suppressed in silent mode
+    pub fn set_status(&self, text: Option<&str>) {
+        if self.silent {
+            return;
+        }
+        let mut footer = OUTPUT_LOCK.lock().unwrap();
+        hide_footer(&footer);
+        footer.status = text.map(|text| {
+            let styled = format!(" {} ", text).reverse();
+            match self.log_color.to_crossterm_color() {
+                Some(c) => styled.with(c).to_string(),
+                None => styled.to_string(),
+            }
+        });
+        show_footer(&footer);
+    }
+
+    /// Forward child process stdout (never suppressed, only colored by highlight rules)
     pub fn forward_stdout(&self, line: &str) {
-        print_line(line);
+        print_line(&self.highlight(line));
     }
 
-    /// Forward child process stderr (never suppressed, never colored)
+    /// Forward child process stderr (never suppressed, only colored by highlight rules)
     pub fn forward_stderr(&self, line: &str) {
-        eprint_line(line);
+        eprint_line(&self.highlight(line));
+    }
+
+    fn highlight<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        match &self.highlighter {
+            Some(highlighter) => highlighter.apply(line),
+            None => Cow::Borrowed(line),
+        }
     }
 }
 
+/// Erase the footer lines so output can be printed in their place
+fn hide_footer(footer: &Footer) {
+    let lines = footer.status.iter().chain(footer.prompt.iter()).count();
+    if lines == 0 {
+        return;
+    }
+
+    let mut handle = io::stdout().lock();
+    let _ = write!(handle, "\r\x1b[2K");
+    for _ in 1..lines {
+        let _ = write!(handle, "\x1b[1A\x1b[2K");
+    }
+    let _ = handle.flush();
+}
+
+/// Draw the footer lines (without a trailing newline) below the last printed line
+fn show_footer(footer: &Footer) {
+    let lines: Vec<&String> = footer.status.iter().chain(footer.prompt.iter()).collect();
+    if lines.is_empty() {
+        return;
+    }
+
+    let mut handle = io::stdout().lock();
+    let _ = write!(
+        handle,
+        "{}",
+        lines
+            .iter()
+            .map(|line| line.as_str())
+            .collect::<Vec<_>>()
+            .join("\r\n")
+    );
+    let _ = handle.flush();
+}
+
 /// Print a line to stdout with proper synchronization and raw mode support
 pub fn print_line(msg: &str) {
-    let _guard = OUTPUT_LOCK.lock().unwrap();
+    let footer = OUTPUT_LOCK.lock().unwrap();
+    hide_footer(&footer);
     let stdout = io::stdout();
     let mut handle = stdout.lock();
 
@@ -128,11 +256,13 @@ pub fn print_line(msg: &str) {
     // Writing \r\n works fine in both raw and normal mode
     let _ = writeln!(handle, "{}\r", msg);
     let _ = handle.flush();
+    show_footer(&footer);
 }
 
 /// Print a line to stderr with proper synchronization and raw mode support
 pub fn eprint_line(msg: &str) {
-    let _guard = OUTPUT_LOCK.lock().unwrap();
+    let footer = OUTPUT_LOCK.lock().unwrap();
+    hide_footer(&footer);
     let stderr = io::stderr();
     let mut handle = stderr.lock();
 
@@ -140,11 +270,13 @@ pub fn eprint_line(msg: &str) {
     // Writing \r\n works fine in both raw and normal mode
     let _ = writeln!(handle, "{}\r", msg);
     let _ = handle.flush();
+    show_footer(&footer);
 }
 
 /// Print a line to stdout with color support
 pub fn print_line_colored(msg: &str, color: LogColor) {
-    let _guard = OUTPUT_LOCK.lock().unwrap();
+    let footer = OUTPUT_LOCK.lock().unwrap();
+    hide_footer(&footer);
     let stdout = io::stdout();
     let mut handle = stdout.lock();
 
@@ -159,11 +291,13 @@ pub fn print_line_colored(msg: &str, color: LogColor) {
     }
 
     let _ = handle.flush();
+    show_footer(&footer);
 }
 
 /// Print a line to stderr with color support
 pub fn eprint_line_colored(msg: &str, color: LogColor) {
-    let _guard = OUTPUT_LOCK.lock().unwrap();
+    let footer = OUTPUT_LOCK.lock().unwrap();
+    hide_footer(&footer);
     let stderr = io::stderr();
     let mut handle = stderr.lock();
 
@@ -178,6 +312,64 @@ pub fn eprint_line_colored(msg: &str, color: LogColor) {
     }
 
     let _ = handle.flush();
+    show_footer(&footer);
+}
+
+/// Print a highlighted banner line to stdout, framed by empty lines
+pub fn print_banner(msg: &str, color: LogColor) {
+    let footer = OUTPUT_LOCK.lock().unwrap();
+    hide_footer(&footer);
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+
+    let _ = writeln!(handle, "\r");
+    if let Some(c) = color.to_crossterm_color() {
+        let _ = crossterm::execute!(handle, SetForegroundColor(c));
+    }
+    let _ = crossterm::execute!(handle, SetAttribute(Attribute::Reverse));
+    let _ = crossterm::execute!(handle, SetAttribute(Attribute::Bold));
+
+    let _ = write!(handle, "  {}  ", msg);
+
+    let _ = crossterm::execute!(handle, SetAttribute(Attribute::Reset), ResetColor);
+    let _ = writeln!(handle, "\r");
+    let _ = writeln!(handle, "\r");
+    let _ = handle.flush();
+    show_footer(&footer);
+}
+
+/// Print a framed panel to stdout in one go, so child output can't interleave
+pub fn print_panel(title: &str, lines: &[String], color: LogColor) {
+    let footer = OUTPUT_LOCK.lock().unwrap();
+    hide_footer(&footer);
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+
+    let width = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .chain(std::iter::once(title.chars().count() + 2))
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    if let Some(c) = color.to_crossterm_color() {
+        let _ = crossterm::execute!(handle, SetForegroundColor(c));
+    }
+
+    let title_rule = "─".repeat(width - title.chars().count() - 3);
+    let _ = writeln!(handle, "┌─ {} {}┐\r", title, title_rule);
+    for line in lines {
+        let padding = " ".repeat(width - line.chars().count() - 1);
+        let _ = writeln!(handle, "│ {}{}│\r", line, padding);
+    }
+    let _ = writeln!(handle, "└{}┘\r", "─".repeat(width));
+
+    if color.to_crossterm_color().is_some() {
+        let _ = crossterm::execute!(handle, ResetColor);
+    }
+    let _ = handle.flush();
+    show_footer(&footer);
 }
 
 /// Macro to replace println! with synchronized output
@@ -0,0 +1,237 @@
+use crate::action::Action;
+use crate::signals::parse_signal;
+use crate::units::parse_duration;
+use anyhow::{Context, Result};
+use crossterm::event::{KeyCode, KeyModifiers};
+
+/// Command names offered by tab completion, besides the action names
+const COMMANDS: [&str; 4] = ["resume", "signal", "env", "debounce"];
+
+/// Signal names offered by tab completion for `:signal`
+const COMPLETION_SIGNALS: [&str; 11] = [
+    "HUP", "INT", "QUIT", "TERM", "KILL", "USR1", "USR2", "STOP", "CONT", "WINCH", "RTMIN+",
+];
+
+/// How many submitted commands the palette remembers
+const HISTORY_SIZE: usize = 100;
+
+/// A command typed into the `:` command palette
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Any hotkey action, e.g. `:restart`, `:stop` or `:quit`
+    Action(Action),
+    /// `:pause` stops the child process group (unlike the hotkey, it doesn't toggle)
+    Pause,
+    /// `:resume` continues a paused child process group
+    Resume,
+    /// `:signal HUP` sends a signal to the child process group
+    Signal(i32),
+    /// `:env set KEY=VALUE` sets a variable for future runs
+    EnvSet(String, String),
+    /// `:env unset KEY` removes a variable for future runs
+    EnvUnset(String),
+    /// `:env` lists the variables changed at runtime
+    EnvList,
+    /// `:debounce 500` changes the restart debounce window (milliseconds or a duration)
+    Debounce(u64),
+}
+
+impl Command {
+    /// Parse a palette line (without the leading `:`)
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut words = line.split_whitespace();
+        let name = words.next().context("Empty command")?;
+        let rest: Vec<&str> = words.collect();
+
+        let command = match (name, rest.as_slice()) {
+            ("pause", []) => Command::Pause,
+            ("resume" | "continue", []) => Command::Resume,
+            ("signal" | "sig", [signal]) => Command::Signal(parse_signal(signal)?),
+            ("signal" | "sig", _) => anyhow::bail!("Usage: signal <NAME>, e.g. signal HUP"),
+            ("env", []) => Command::EnvList,
+            ("env", ["set", assignment]) => {
+                let (key, value) = assignment
+                    .split_once('=')
+                    .filter(|(key, _)| !key.is_empty())
+                    .context("Usage: env set KEY=VALUE")?;
+                Command::EnvSet(key.to_string(), value.to_string())
+            }
+            ("env", ["unset", key]) => Command::EnvUnset(key.to_string()),
+            ("env", _) => anyhow::bail!("Usage: env [set KEY=VALUE | unset KEY]"),
+            ("debounce", [value]) => Command::Debounce(parse_debounce(value)?),
+            ("debounce", _) => anyhow::bail!("Usage: debounce <MILLISECONDS|DURATION>"),
+            (name, []) => match Action::parse(name) {
+                Ok(Action::Palette) | Err(_) => anyhow::bail!(
+                    "Unknown command '{}'. Supported: {}",
+                    name,
+                    command_names().join(", ")
+                ),
+                Ok(action) => Command::Action(action),
+            },
+            (name, _) => anyhow::bail!("Command '{}' takes no arguments", name),
+        };
+        Ok(command)
+    }
+}
+
+/// A plain number is milliseconds (like --restart-debounce-ms), otherwise a duration
+fn parse_debounce(value: &str) -> Result<u64> {
+    match value.parse::<u64>() {
+        Ok(ms) => Ok(ms),
+        Err(_) => Ok(parse_duration(value)?.as_millis() as u64),
+    }
+}
+
+/// All top-level command names, in completion order
+fn command_names() -> Vec<&'static str> {
+    Action::ALL
+        .iter()
+        .filter(|action| **action != Action::Palette)
+        .map(|action| action.name())
+        .chain(COMMANDS)
+        .collect()
+}
+
+/// What a key press did to the prompt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PromptEvent {
+    /// The line was edited, redraw it
+    Edited,
+    /// Tab completion found several candidates
+    Candidates(Vec<String>),
+    /// Enter was pressed with this line
+    Submit(String),
+    /// The prompt was closed without a command
+    Cancel,
+}
+
+/// Line editor state for the command palette, with history and tab completion
+#[derive(Debug, Default)]
+pub struct Prompt {
+    line: String,
+    history: Vec<String>,
+    /// Index into history while browsing with up/down
+    history_pos: Option<usize>,
+}
+
+impl Prompt {
+    /// The prompt as it should be drawn
+    pub fn render(&self) -> String {
+        format!(":{}", self.line)
+    }
+
+    /// Start editing a new, empty line
+    pub fn open(&mut self) {
+        self.line.clear();
+        self.history_pos = None;
+    }
+
+    /// Apply a key press to the line
+    pub fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> PromptEvent {
+        match code {
+            KeyCode::Esc => PromptEvent::Cancel,
+            KeyCode::Enter => {
+                let line = self.line.trim().to_string();
+                if line.is_empty() {
+                    return PromptEvent::Cancel;
+                }
+                self.history.retain(|entry| *entry != line);
+                self.history.push(line.clone());
+                if self.history.len() > HISTORY_SIZE {
+                    self.history.remove(0);
+                }
+                PromptEvent::Submit(line)
+            }
+            KeyCode::Backspace if self.line.is_empty() => PromptEvent::Cancel,
+            KeyCode::Backspace => {
+                self.line.pop();
+                PromptEvent::Edited
+            }
+            KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => {
+                self.line.clear();
+                PromptEvent::Edited
+            }
+            KeyCode::Char(c) if !modifiers.contains(KeyModifiers::CONTROL) => {
+                self.line.push(c);
+                PromptEvent::Edited
+            }
+            KeyCode::Up => {
+                let pos = match self.history_pos {
+                    Some(pos) => pos.saturating_sub(1),
+                    None if self.history.is_empty() => return PromptEvent::Edited,
+                    None => self.history.len() - 1,
+                };
+                self.history_pos = Some(pos);
+                self.line = self.history[pos].clone();
+                PromptEvent::Edited
+            }
+            KeyCode::Down => {
+                match self.history_pos {
+                    Some(pos) if pos + 1 < self.history.len() => {
+                        self.history_pos = Some(pos + 1);
+                        self.line = self.history[pos + 1].clone();
+                    }
+                    Some(_) => {
+                        self.history_pos = None;
+                        self.line.clear();
+                    }
+                    None => {}
+                }
+                PromptEvent::Edited
+            }
+            KeyCode::Tab => self.complete(),
+            _ => PromptEvent::Edited,
+        }
+    }
+
+    /// Complete the word under the cursor (the last word of the line)
+    fn complete(&mut self) -> PromptEvent {
+        let (prefix, word) = match self.line.rfind(' ') {
+            Some(pos) => self.line.split_at(pos + 1),
+            None => ("", self.line.as_str()),
+        };
+
+        let previous: Vec<&str> = prefix.split_whitespace().collect();
+        let candidates: Vec<&str> = match previous.as_slice() {
+            [] => command_names(),
+            ["signal" | "sig"] => COMPLETION_SIGNALS.to_vec(),
+            ["env"] => vec!["set", "unset"],
+            _ => Vec::new(),
+        };
+
+        let upper = word.to_uppercase();
+        let matches: Vec<&str> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word) || candidate.starts_with(&upper))
+            .collect();
+
+        match matches.as_slice() {
+            [] => PromptEvent::Edited,
+            [single] => {
+                self.line = format!("{}{} ", prefix, single);
+                PromptEvent::Edited
+            }
+            _ => {
+                let common = common_prefix(&matches);
+                if common.len() > word.len() {
+                    self.line = format!("{}{}", prefix, common);
+                    PromptEvent::Edited
+                } else {
+                    PromptEvent::Candidates(matches.iter().map(|m| m.to_string()).collect())
+                }
+            }
+        }
+    }
+}
+
+fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let first = words[0];
+    let len = words.iter().skip(1).fold(first.len(), |len, word| {
+        first[..len]
+            .char_indices()
+            .zip(word.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(len.min(word.len()), |((i, _), _)| i)
+    });
+    &first[..len]
+}
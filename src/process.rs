@@ -1,104 +1,382 @@
+use crate::buffer::{OutputBuffer, Stream};
+use crate::env::{changed_keys, Environment};
+use crate::error::{Error, Result};
+use crate::events::{Events, StopReason, SupervisorEvent};
+use crate::filter::OutputFilter;
+use crate::launch::LaunchOptions;
 use crate::output::Output;
-use anyhow::{Context, Result};
-use tokio::io::{AsyncBufReadExt, BufReader};
+use crate::triggers::TriggerSet;
+use nix::sys::signal::Signal;
+use std::collections::BTreeMap;
+use std::os::unix::process::ExitStatusExt;
+use std::process::ExitStatus;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio::process::{Child, Command};
-use tokio::time::{timeout, Duration};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Duration, Instant};
 
+/// Spawns, restarts and stops the child process, forwarding its output.
+/// Get one from [`SupervisorBuilder::build_process_manager`](crate::SupervisorBuilder::build_process_manager)
+/// to manage a child without the supervisor event loop.
 pub struct ProcessManager {
     command: String,
     args: Vec<String>,
     child: Option<Child>,
     output: Output,
+    buffer: OutputBuffer,
+    triggers: TriggerSet,
+    filter: OutputFilter,
+    forwarders: Vec<JoinHandle<()>>,
+    environment: Environment,
+    launch: LaunchOptions,
+    events: Events,
+    last_env: Option<BTreeMap<String, String>>,
+    env: BTreeMap<String, Option<String>>,
+    run: u64,
+    started_at: Option<Instant>,
+    paused: bool,
+    last_status: Option<ExitStatus>,
 }
 
 impl ProcessManager {
-    pub fn new(command: String, args: Vec<String>, output: Output) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        command: String,
+        args: Vec<String>,
+        output: Output,
+        buffer: OutputBuffer,
+        triggers: TriggerSet,
+        filter: OutputFilter,
+        environment: Environment,
+        launch: LaunchOptions,
+        events: Events,
+    ) -> Self {
         Self {
             command,
             args,
             child: None,
             output,
+            buffer,
+            triggers,
+            filter,
+            forwarders: Vec::new(),
+            environment,
+            launch,
+            events,
+            last_env: None,
+            env: BTreeMap::new(),
+            run: 0,
+            started_at: None,
+            paused: false,
+            last_status: None,
         }
     }
 
     pub async fn spawn(&mut self) -> Result<()> {
         if self.child.is_some() {
-            anyhow::bail!("Process already running");
+            return Err(Error::AlreadyRunning);
         }
+        let env = self.environment.resolve().await.map_err(|e| Error::Spawn {
+            command: self.command.clone(),
+            source: e.context("Failed to load the child environment").into(),
+        })?;
+        self.spawn_with_env(env).await
+    }
+
+    /// Check that the command exists and is executable without starting it, for when
+    /// the first start is deferred. Bare names are looked up in supi's PATH.
+    pub fn check_command(&self) -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let is_executable = |path: &std::path::Path| {
+            std::fs::metadata(path)
+                .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        };
+
+        let found = if self.command.contains('/') {
+            let path = std::path::Path::new(&self.command);
+            match self.launch.cwd() {
+                Some(cwd) if path.is_relative() => is_executable(&cwd.join(path)),
+                _ => is_executable(path),
+            }
+        } else {
+            std::env::var_os("PATH").is_some_and(|paths| {
+                std::env::split_paths(&paths).any(|dir| is_executable(&dir.join(&self.command)))
+            })
+        };
 
+        if !found {
+            return Err(Error::Spawn {
+                command: self.command.clone(),
+                source: "not an executable file or a command in PATH".into(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Spawn the child with the variables resolved from `--env`, `--env-file` and
+    /// `--env-command` (on top of the inherited environment) and the runtime overrides
+    async fn spawn_with_env(&mut self, env: BTreeMap<String, String>) -> Result<()> {
         self.output.log(&format!(
             "[supi] Starting child process: {} {:?}",
             self.command, self.args
         ));
 
-        let mut child = Command::new(&self.command)
+        if let Some(last_env) = &self.last_env {
+            let changed = changed_keys(last_env, &env);
+            if !changed.is_empty() {
+                self.output.log(&format!(
+                    "[supi] Environment reloaded, changed: {}",
+                    changed.join(", ")
+                ));
+            }
+        }
+
+        let mut command = Command::new(&self.command);
+        if self.environment.is_cleared() {
+            command.env_clear();
+            for (key, value) in std::env::vars_os() {
+                if key
+                    .to_str()
+                    .is_some_and(|key| self.environment.inherits(key))
+                {
+                    command.env(key, value);
+                }
+            }
+        }
+        self.launch.apply(&mut command);
+        command.envs(&env);
+        self.last_env = Some(env);
+        for (key, value) in &self.env {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+
+        let mut child = command
             .args(&self.args)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .kill_on_drop(true)
             .process_group(0) // Create new process group
             .spawn()
-            .context("Failed to spawn child process")?;
+            .map_err(|e| Error::Spawn {
+                command: self.command.clone(),
+                source: e.into(),
+            })?;
 
         // Get stdout/stderr handles
-        let stdout = child
-            .stdout
-            .take()
-            .context("Failed to capture child stdout")?;
-        let stderr = child
-            .stderr
-            .take()
-            .context("Failed to capture child stderr")?;
-
-        // Clone output for spawned tasks
-        let output_stdout = self.output.clone();
-        let output_stderr = self.output.clone();
+        let stdout = child.stdout.take().ok_or_else(|| Error::Spawn {
+            command: self.command.clone(),
+            source: "stdout was not captured".into(),
+        })?;
+        let stderr = child.stderr.take().ok_or_else(|| Error::Spawn {
+            command: self.command.clone(),
+            source: "stderr was not captured".into(),
+        })?;
 
-        // Spawn tasks to forward output
-        tokio::spawn(async move {
-            let reader = BufReader::new(stdout);
-            let mut lines = reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                output_stdout.forward_stdout(&line);
-            }
-        });
+        self.run += 1;
+        self.started_at = Some(Instant::now());
 
-        tokio::spawn(async move {
-            let reader = BufReader::new(stderr);
-            let mut lines = reader.lines();
-            while let Ok(Some(line)) = lines.next_line().await {
-                output_stderr.forward_stderr(&line);
-            }
-        });
+        // Spawn tasks to forward output
+        self.forwarders = vec![
+            spawn_forwarder(
+                stdout,
+                Stream::Stdout,
+                self.run,
+                self.output.clone(),
+                self.buffer.clone(),
+                self.triggers.clone(),
+                self.filter.clone(),
+                self.events.clone(),
+            ),
+            spawn_forwarder(
+                stderr,
+                Stream::Stderr,
+                self.run,
+                self.output.clone(),
+                self.buffer.clone(),
+                self.triggers.clone(),
+                self.filter.clone(),
+                self.events.clone(),
+            ),
+        ];
 
         let pid = child.id().unwrap_or(0);
         self.child = Some(child);
 
         self.output
             .log(&format!("[supi] Child process running (PID: {})", pid));
+        self.events
+            .emit(SupervisorEvent::Spawned { run: self.run, pid });
 
         Ok(())
     }
 
-    pub async fn wait(&mut self) -> Result<std::process::ExitStatus> {
-        if let Some(child) = &mut self.child {
-            let status = child.wait().await.context("Failed to wait on child")?;
-            self.child = None;
-            Ok(status)
-        } else {
-            anyhow::bail!("No process running")
+    /// Receive the events of this child: spawns, output, stops and exits
+    pub fn subscribe(&self) -> broadcast::Receiver<SupervisorEvent> {
+        self.events.subscribe()
+    }
+
+    pub(crate) fn events(&self) -> &Events {
+        &self.events
+    }
+
+    /// Record how a run ended and tell subscribers
+    fn finish_run(&mut self, status: Option<ExitStatus>, stopped: Option<StopReason>) {
+        self.last_status = status;
+        if let Some(status) = status {
+            self.events.emit(SupervisorEvent::Exited {
+                run: self.run,
+                exit_code: exit_code(status),
+                stopped,
+            });
+        }
+    }
+
+    /// Wait for the child to exit. Cancel-safe: if the future is dropped (e.g. because
+    /// another `select!` branch won), the next call picks up where this one stopped.
+    pub async fn wait(&mut self) -> Result<ExitStatus> {
+        let child = self.child.as_mut().ok_or(Error::NotRunning)?;
+        // tokio caches the exit status, so waiting again after a cancel is fine
+        let status = child.wait().await.map_err(|source| Error::Process {
+            action: "wait on",
+            source,
+        })?;
+
+        self.drain_forwarders().await;
+
+        self.child = None;
+        self.paused = false;
+        self.finish_run(Some(status), None);
+        Ok(status)
+    }
+
+    /// Give the forwarders a moment to drain the remaining output, so everything the
+    /// child printed is shown and in the buffer. Grandchildren may still hold the
+    /// pipes open, so don't wait forever.
+    async fn drain_forwarders(&mut self) {
+        while let Some(handle) = self.forwarders.first_mut() {
+            let _ = timeout(Duration::from_millis(500), handle).await;
+            self.forwarders.remove(0);
         }
     }
 
     pub async fn restart(&mut self) -> Result<()> {
+        self.restart_for(StopReason::Restart).await
+    }
+
+    /// Restart the child, telling subscribers why the current run is stopped
+    pub(crate) async fn restart_for(&mut self, reason: StopReason) -> Result<()> {
+        // Re-read the environment first, so a broken env file doesn't take the
+        // running child down
+        let env = match self.environment.resolve().await {
+            Ok(env) => env,
+            Err(e) => {
+                self.output.elog(&format!(
+                    "[supi] Not restarting, failed to load the child environment: {:#}",
+                    e
+                ));
+                return Ok(());
+            }
+        };
+
         self.output.log("[supi] Restarting child process...");
-        self.shutdown().await?;
-        self.spawn().await?;
+        self.stop(reason).await?;
+        self.spawn_with_env(env).await?;
+        Ok(())
+    }
+
+    /// Kill the child process group immediately with SIGKILL, skipping the graceful stop
+    pub async fn kill(&mut self) -> Result<()> {
+        if let Some(mut child) = self.child.take() {
+            self.output.log("[supi] Force killing child process...");
+
+            #[cfg(unix)]
+            if let Some(pid) = child.id() {
+                use nix::sys::signal::{kill, Signal};
+                use nix::unistd::Pid;
+
+                let _ = kill(Pid::from_raw(-(pid as i32)), Signal::SIGKILL);
+                self.events
+                    .emit(SupervisorEvent::Killed { run: self.run, pid });
+            }
+
+            child.kill().await.map_err(|source| Error::Process {
+                action: "kill",
+                source,
+            })?;
+            let status = child.wait().await.ok();
+            self.paused = false;
+            self.drain_forwarders().await;
+            self.finish_run(status, Some(StopReason::Kill));
+            self.output.log("[supi] Child process killed");
+        }
         Ok(())
     }
 
+    /// Freeze (SIGSTOP) or continue (SIGCONT) the whole child process group.
+    /// Returns false if it already was in the requested state.
+    pub fn set_paused(&mut self, paused: bool) -> Result<bool> {
+        if self.paused == paused {
+            return Ok(false);
+        }
+
+        let signal = if paused {
+            Signal::SIGSTOP
+        } else {
+            Signal::SIGCONT
+        };
+        self.send_signal(signal as i32)?;
+        self.paused = paused;
+
+        Ok(true)
+    }
+
+    /// Send a signal (by number, so realtime signals work too) to the whole child process group
+    pub fn send_signal(&self, signal: i32) -> Result<()> {
+        use nix::errno::Errno;
+
+        let pid = self
+            .child
+            .as_ref()
+            .and_then(|child| child.id())
+            .ok_or(Error::NotRunning)?;
+
+        // SAFETY: kill(2) has no memory safety requirements
+        Errno::result(unsafe { nix::libc::kill(-(pid as i32), signal) })
+            .map(drop)
+            .map_err(|errno| Error::Signal {
+                signal,
+                source: errno.into(),
+            })
+    }
+
+    /// Set an environment variable for future runs of the child
+    pub fn set_env(&mut self, key: String, value: String) {
+        self.env.insert(key, Some(value));
+    }
+
+    /// Remove an environment variable for future runs of the child
+    pub fn unset_env(&mut self, key: String) {
+        self.env.insert(key, None);
+    }
+
+    /// Environment changes made at runtime (`None` means removed)
+    pub fn env_overrides(&self) -> &BTreeMap<String, Option<String>> {
+        &self.env
+    }
+
     pub async fn shutdown(&mut self) -> Result<()> {
+        self.stop(StopReason::Stop).await
+    }
+
+    /// Stop the child gracefully, telling subscribers why
+    pub(crate) async fn stop(&mut self, reason: StopReason) -> Result<()> {
         if let Some(mut child) = self.child.take() {
+            let paused = std::mem::take(&mut self.paused);
             self.output
                 .log("[supi] Stopping child process gracefully...");
 
@@ -109,13 +387,28 @@ impl ProcessManager {
                 use nix::unistd::Pid;
 
                 if let Some(pid) = child.id() {
+                    self.events.emit(SupervisorEvent::Stopping {
+                        run: self.run,
+                        pid,
+                        reason,
+                    });
+
+                    // A stopped process group can't act on SIGTERM, continue it first
+                    if paused {
+                        self.output
+                            .log("[supi] Resuming paused child process group before stopping");
+                        let _ = kill(Pid::from_raw(-(pid as i32)), Signal::SIGCONT);
+                    }
+
                     // Send SIGTERM to entire process group for graceful shutdown
                     // Negative PID targets the process group
                     let _ = kill(Pid::from_raw(-(pid as i32)), Signal::SIGTERM);
 
                     // Wait up to 5 seconds for graceful exit
                     match timeout(Duration::from_secs(5), child.wait()).await {
-                        Ok(Ok(_status)) => {
+                        Ok(Ok(status)) => {
+                            self.drain_forwarders().await;
+                            self.finish_run(Some(status), Some(reason));
                             self.output.log("[supi] Child process stopped gracefully");
                             return Ok(());
                         }
@@ -128,14 +421,21 @@ impl ProcessManager {
                                 .log("[supi] Child process didn't stop gracefully, forcing...");
                             // Force kill the entire process group
                             let _ = kill(Pid::from_raw(-(pid as i32)), Signal::SIGKILL);
+                            self.events
+                                .emit(SupervisorEvent::Killed { run: self.run, pid });
                         }
                     }
                 }
             }
 
             // Force kill if graceful shutdown failed or on non-Unix platforms
-            child.kill().await.context("Failed to kill child process")?;
-            let _ = child.wait().await;
+            child.kill().await.map_err(|source| Error::Process {
+                action: "kill",
+                source,
+            })?;
+            let status = child.wait().await.ok();
+            self.drain_forwarders().await;
+            self.finish_run(status, Some(reason));
             self.output.log("[supi] Child process stopped");
         }
         Ok(())
@@ -144,4 +444,78 @@ impl ProcessManager {
     pub fn is_running(&self) -> bool {
         self.child.is_some()
     }
+
+    /// PID of the running child process
+    pub fn pid(&self) -> Option<u32> {
+        self.child.as_ref().and_then(|child| child.id())
+    }
+
+    /// Number of the current (or most recent) run, starting at 1
+    pub fn run(&self) -> u64 {
+        self.run
+    }
+
+    /// How long the running child has been up
+    pub fn uptime(&self) -> Option<Duration> {
+        self.started_at
+            .filter(|_| self.child.is_some())
+            .map(|started| started.elapsed())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Exit code of the most recent run, in shell convention (128 + signal if it was killed)
+    pub fn last_exit_code(&self) -> Option<i32> {
+        self.last_status.map(exit_code)
+    }
+}
+
+/// Shell-style exit code for an exit status, e.g. 143 for a child killed by SIGTERM
+fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .or_else(|| status.signal().map(|signal| 128 + signal))
+        .unwrap_or(1)
+}
+
+/// Forward lines from a child stream to the terminal, recording them in the output buffer,
+/// checking them against the trigger rules and sending them to event subscribers. Lines
+/// hidden by the output filter are still buffered, checked and sent.
+#[allow(clippy::too_many_arguments)]
+fn spawn_forwarder<R>(
+    reader: R,
+    stream: Stream,
+    run: u64,
+    output: Output,
+    buffer: OutputBuffer,
+    triggers: TriggerSet,
+    filter: OutputFilter,
+    events: Events,
+) -> JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            buffer.push(run, stream, &line);
+            triggers.check(stream, &line);
+            if events.has_subscribers() {
+                events.emit(SupervisorEvent::Output {
+                    run,
+                    stream,
+                    line: line.clone(),
+                });
+            }
+            if !filter.should_show(stream, &line) {
+                continue;
+            }
+            match stream {
+                Stream::Stdout => output.forward_stdout(&line),
+                Stream::Stderr => output.forward_stderr(&line),
+            }
+        }
+    })
 }
@@ -0,0 +1,152 @@
+use crate::output::Output;
+use anyhow::{Context, Result};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::Pid;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tokio::signal::unix::{signal, Signal, SignalKind};
+use tokio::time::{interval, Duration, Interval};
+
+/// Signals forwarded to the child by default in init mode, like tini and dumb-init do
+pub const INIT_FORWARD_SIGNALS: [i32; 3] = [
+    signal_hook::consts::signal::SIGHUP,
+    signal_hook::consts::signal::SIGUSR2,
+    signal_hook::consts::signal::SIGWINCH,
+];
+
+/// How often zombies are looked for even without SIGCHLD, in case one was coalesced
+const SWEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// PIDs of helper processes supi spawns itself, like trigger commands. They are waited
+/// on by tokio, so the reaper must not take their exit status. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct OwnChildren {
+    pids: Arc<Mutex<HashSet<u32>>>,
+}
+
+impl OwnChildren {
+    /// Register a helper right after spawning it, before the reaper can see it exit
+    pub fn insert(&self, pid: u32) {
+        self.pids.lock().unwrap().insert(pid);
+    }
+
+    /// Forget a helper once it has been waited on
+    pub fn remove(&self, pid: u32) {
+        self.pids.lock().unwrap().remove(&pid);
+    }
+
+    fn contains(&self, pid: u32) -> bool {
+        self.pids.lock().unwrap().contains(&pid)
+    }
+}
+
+/// Reaps orphaned descendants that were re-parented to supi, either because it runs
+/// as PID 1 or because it is a child subreaper. The direct child and supi's own
+/// helper processes are left alone, they are waited on where they were spawned.
+pub struct Reaper {
+    sigchld: Signal,
+    sweep: Interval,
+    output: Output,
+    own_children: OwnChildren,
+}
+
+impl Reaper {
+    /// Become a child subreaper (a no-op for PID 1, which already is one) and start
+    /// listening for SIGCHLD
+    pub fn new(output: Output, own_children: OwnChildren) -> Result<Self> {
+        set_subreaper()?;
+        // tokio's own SIGCHLD listener, which it shares with the child process driver
+        let sigchld = signal(SignalKind::child()).context("Failed to create SIGCHLD handler")?;
+        Ok(Self {
+            sigchld,
+            sweep: interval(SWEEP_INTERVAL),
+            output,
+            own_children,
+        })
+    }
+
+    /// Wait until a descendant may have become a zombie
+    pub async fn next(&mut self) {
+        tokio::select! {
+            _ = self.sigchld.recv() => {}
+            _ = self.sweep.tick() => {}
+        }
+    }
+
+    /// Reap every zombie child of supi except `direct_child` and supi's own helpers,
+    /// returning how many were reaped
+    pub fn reap_orphans(&self, direct_child: Option<u32>) -> usize {
+        let mut reaped = 0;
+        for pid in zombie_children() {
+            if Some(pid) == direct_child || self.own_children.contains(pid) {
+                continue;
+            }
+            match waitpid(Pid::from_raw(pid as i32), Some(WaitPidFlag::WNOHANG)) {
+                Ok(WaitStatus::Exited(_, code)) => {
+                    self.output.log(&format!(
+                        "[supi] Reaped orphaned process {} (exit code {})",
+                        pid, code
+                    ));
+                    reaped += 1;
+                }
+                Ok(WaitStatus::Signaled(_, signal, _)) => {
+                    self.output.log(&format!(
+                        "[supi] Reaped orphaned process {} (killed by {})",
+                        pid,
+                        signal.as_str()
+                    ));
+                    reaped += 1;
+                }
+                _ => {}
+            }
+        }
+        reaped
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_subreaper() -> Result<()> {
+    nix::sys::prctl::set_child_subreaper(true).context("Failed to set PR_SET_CHILD_SUBREAPER")
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_subreaper() -> Result<()> {
+    if std::process::id() == 1 {
+        return Ok(());
+    }
+    anyhow::bail!("child subreapers are only supported on Linux")
+}
+
+/// PIDs of zombie processes whose parent is supi, read from /proc.
+/// Only zombies are returned, so a process that is still running is never waited on.
+#[cfg(target_os = "linux")]
+fn zombie_children() -> Vec<u32> {
+    let own_pid = std::process::id();
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| {
+            let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) else {
+                return false;
+            };
+            // The command name may contain spaces and parentheses, the fields after
+            // the last ')' are: state ppid ...
+            let mut fields = stat
+                .rsplit_once(')')
+                .map(|(_, rest)| rest.split_whitespace())
+                .into_iter()
+                .flatten();
+            let state = fields.next();
+            let ppid = fields.next().and_then(|ppid| ppid.parse::<u32>().ok());
+            state == Some("Z") && ppid == Some(own_pid)
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn zombie_children() -> Vec<u32> {
+    Vec::new()
+}
@@ -0,0 +1,104 @@
+use crate::error::Error;
+use crate::units::{format_duration, parse_duration};
+use anyhow::Context;
+use chrono::{DateTime, Local};
+use croner::Cron;
+use tokio::time::{Duration, Instant};
+
+/// When scheduled restarts happen
+enum Cadence {
+    Every(String, Duration),
+    Cron(String, Box<Cron>),
+}
+
+/// Restarts the child on a fixed interval (`--restart-every`) or a cron pattern
+/// (`--restart-cron`, in local time)
+pub struct RestartSchedule {
+    cadence: Cadence,
+    next: Instant,
+    next_at: DateTime<Local>,
+}
+
+impl RestartSchedule {
+    /// Parse `--restart-every` and schedule the first restart
+    pub fn every(spec: &str) -> crate::Result<Self> {
+        let every = parse_duration(spec)
+            .ok()
+            .filter(|every| !every.is_zero())
+            .context("expected a duration like 6h")
+            .map_err(|e| Error::invalid_option("restart_every", spec, e))?;
+        Self::new(Cadence::Every(spec.to_string(), every))
+    }
+
+    /// Parse `--restart-cron` and schedule the first restart
+    pub fn cron(pattern: &str) -> crate::Result<Self> {
+        let cron = Cron::new(pattern)
+            .with_seconds_optional()
+            .parse()
+            .map_err(|e| Error::invalid_option("restart_cron", pattern, e))?;
+        Self::new(Cadence::Cron(pattern.to_string(), Box::new(cron)))
+    }
+
+    fn new(cadence: Cadence) -> crate::Result<Self> {
+        let mut schedule = Self {
+            cadence,
+            next: Instant::now(),
+            next_at: Local::now(),
+        };
+        schedule.advance()?;
+        Ok(schedule)
+    }
+
+    /// Summary for the startup log, e.g. `every 6h` or `cron '0 3 * * *'`
+    pub fn describe(&self) -> String {
+        match &self.cadence {
+            Cadence::Every(_, every) => format!("every {}", format_duration(*every)),
+            Cadence::Cron(pattern, _) => format!("cron '{}'", pattern),
+        }
+    }
+
+    /// When the next restart happens, e.g. `2024-05-01 03:00:00 (in 5h 12m)`
+    pub fn describe_next(&self) -> String {
+        format!(
+            "{} (in {})",
+            self.next_at.format("%Y-%m-%d %H:%M:%S"),
+            format_duration(self.next.saturating_duration_since(Instant::now()))
+        )
+    }
+
+    /// Wait until the next scheduled restart is due
+    pub async fn wait(&self) {
+        tokio::time::sleep_until(self.next).await;
+    }
+
+    /// Schedule the restart after the one that just fired. Intervals keep their
+    /// cadence from supi's start; cron patterns use the next matching local time.
+    pub fn advance(&mut self) -> crate::Result<()> {
+        let now = Instant::now();
+        match &self.cadence {
+            Cadence::Every(spec, every) => {
+                let too_large = || Error::invalid_option("restart_every", spec, "too large");
+                // Skip missed slots instead of firing several restarts in a row
+                while self.next <= now {
+                    self.next = self.next.checked_add(*every).ok_or_else(too_large)?;
+                }
+                let until = self.next - now;
+                self.next_at = chrono::Duration::from_std(until)
+                    .ok()
+                    .and_then(|until| Local::now().checked_add_signed(until))
+                    .ok_or_else(too_large)?;
+            }
+            Cadence::Cron(pattern, cron) => {
+                let wall_now = Local::now();
+                let next_at = cron
+                    .find_next_occurrence(&wall_now, false)
+                    .context("no upcoming time matches")
+                    .map_err(|e| Error::invalid_option("restart_cron", pattern, e))?;
+                let until = (next_at - wall_now).to_std().unwrap_or_default();
+                self.next = now + until;
+                self.next_at = next_at;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,612 @@
+use anyhow::{Context, Result};
+use nix::unistd::{Pid, Uid};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use supi_cli::Output;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{broadcast, mpsc};
+
+/// Set by `supi --detach` for the background session host it starts
+const HOST_ENV: &str = "SUPI_SESSION_HOST";
+
+/// Set by the session host for the supervisor it runs, so that one doesn't detach again
+const SESSION_ENV: &str = "SUPI_SESSION";
+
+/// Overrides where the session registry lives
+const DIR_ENV: &str = "SUPI_SESSION_DIR";
+
+/// How much recent output is replayed to a terminal that attaches
+const REPLAY_BYTES: usize = 256 * 1024;
+
+/// Largest frame accepted from the socket, well above the replay and output chunks
+/// that are actually sent, so a peer can't make supi allocate arbitrary memory
+const MAX_FRAME_BYTES: usize = 1024 * 1024;
+
+/// Terminal size of a session until a terminal attaches
+const DEFAULT_SIZE: (u16, u16) = (24, 80);
+
+/// Directory with one `<name>.pid` and `<name>.sock` per detached session
+pub struct Registry {
+    dir: PathBuf,
+}
+
+/// A running detached session
+pub struct Session {
+    pub name: String,
+    /// PID of the supervisor running in the session
+    pub pid: u32,
+    socket: PathBuf,
+}
+
+impl Registry {
+    /// Open the registry in `$SUPI_SESSION_DIR`, `$XDG_RUNTIME_DIR/supi` or
+    /// `/tmp/supi-<uid>`, creating it readable only by the current user
+    pub fn open() -> Result<Self> {
+        let (dir, own_dir) = Self::location();
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&dir)
+            .with_context(|| format!("Failed to create session directory {}", dir.display()))?;
+        // A directory given in $SUPI_SESSION_DIR keeps its mode, supi's own one may
+        // have been created by an older version or another umask
+        if own_dir {
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))
+                .with_context(|| format!("Failed to secure session directory {}", dir.display()))?;
+        }
+        Ok(Self { dir })
+    }
+
+    /// The registry if its directory exists, without creating or changing anything
+    pub fn existing() -> Option<Self> {
+        let (dir, _) = Self::location();
+        dir.is_dir().then_some(Self { dir })
+    }
+
+    /// The registry directory and whether it's supi's own rather than `$SUPI_SESSION_DIR`
+    fn location() -> (PathBuf, bool) {
+        match (
+            std::env::var_os(DIR_ENV),
+            std::env::var_os("XDG_RUNTIME_DIR"),
+        ) {
+            (Some(dir), _) => (PathBuf::from(dir), false),
+            (None, Some(runtime)) => (PathBuf::from(runtime).join("supi"), true),
+            (None, None) => (
+                std::env::temp_dir().join(format!("supi-{}", Uid::current())),
+                true,
+            ),
+        }
+    }
+
+    fn pidfile(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.pid", name))
+    }
+
+    fn socket(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.sock", name))
+    }
+
+    /// Running sessions, sorted by name. Leftovers of sessions that died are removed.
+    pub fn sessions(&self) -> Vec<Session> {
+        self.scan(true)
+    }
+
+    /// Running sessions, sorted by name, leaving the leftovers of sessions that died
+    pub fn running(&self) -> Vec<Session> {
+        self.scan(false)
+    }
+
+    fn scan(&self, prune: bool) -> Vec<Session> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+
+        let mut sessions: Vec<Session> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "pid" {
+                    return None;
+                }
+                let name = path.file_stem()?.to_str()?.to_string();
+                let pid = std::fs::read_to_string(&path).ok()?.trim().parse().ok();
+                let socket = self.socket(&name);
+                match pid {
+                    Some(pid) if is_alive(pid) && socket.exists() => {
+                        Some(Session { name, pid, socket })
+                    }
+                    _ => {
+                        if prune {
+                            let _ = std::fs::remove_file(&path);
+                            let _ = std::fs::remove_file(&socket);
+                        }
+                        None
+                    }
+                }
+            })
+            .collect();
+        sessions.sort_by(|a, b| a.name.cmp(&b.name));
+        sessions
+    }
+
+    /// Find a session by name, or the only running one when no name is given
+    pub fn find(&self, name: Option<&str>) -> Result<Session> {
+        let mut sessions = self.sessions();
+        if let Some(name) = name {
+            return sessions
+                .into_iter()
+                .find(|session| session.name == name)
+                .with_context(|| format!("No session named '{}' is running", name));
+        }
+
+        match sessions.len() {
+            0 => anyhow::bail!("No detached supi sessions are running"),
+            1 => Ok(sessions.remove(0)),
+            _ => anyhow::bail!(
+                "Several sessions are running, pick one: {}",
+                sessions
+                    .iter()
+                    .map(|session| format!("{} (PID {})", session.name, session.pid))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl Session {
+    /// Connect to the session host
+    pub async fn connect(&self) -> Result<UnixStream> {
+        UnixStream::connect(&self.socket)
+            .await
+            .with_context(|| format!("Failed to connect to session '{}'", self.name))
+    }
+}
+
+fn is_alive(pid: u32) -> bool {
+    match nix::sys::signal::kill(Pid::from_raw(pid as i32), None) {
+        Ok(()) => true,
+        Err(errno) => errno == nix::errno::Errno::EPERM,
+    }
+}
+
+/// A message between `supi attach`/`supi detach` and the session host, sent as a
+/// tag byte, a big-endian u32 length and the payload
+#[derive(Debug)]
+pub enum Frame {
+    /// Client: attach a terminal of this size
+    Attach { rows: u16, cols: u16 },
+    /// Client: disconnect all attached terminals
+    Detach,
+    /// Client: keys typed in the attached terminal
+    Input(Vec<u8>),
+    /// Client: the attached terminal was resized
+    Resize { rows: u16, cols: u16 },
+    /// Host: output of the session
+    Output(Vec<u8>),
+    /// Host: the connection ends, with the reason (for `supi detach`, the number of
+    /// terminals that were detached)
+    Closed(String),
+}
+
+impl Frame {
+    pub async fn write_to<W: AsyncWrite + Unpin>(&self, writer: &mut W) -> Result<()> {
+        let size = |rows: u16, cols: u16| [rows.to_be_bytes(), cols.to_be_bytes()].concat();
+        let (tag, payload) = match self {
+            Frame::Attach { rows, cols } => (b'a', size(*rows, *cols)),
+            Frame::Detach => (b'd', Vec::new()),
+            Frame::Input(bytes) => (b'i', bytes.clone()),
+            Frame::Resize { rows, cols } => (b'r', size(*rows, *cols)),
+            Frame::Output(bytes) => (b'o', bytes.clone()),
+            Frame::Closed(reason) => (b'c', reason.as_bytes().to_vec()),
+        };
+        writer.write_u8(tag).await?;
+        writer.write_u32(payload.len() as u32).await?;
+        writer.write_all(&payload).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Read the next frame, `None` when the other side closed the connection
+    pub async fn read_from<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Self>> {
+        let tag = match reader.read_u8().await {
+            Ok(tag) => tag,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let len = reader.read_u32().await? as usize;
+        anyhow::ensure!(
+            len <= MAX_FRAME_BYTES,
+            "Session message of {} bytes is too large",
+            len
+        );
+        let mut payload = vec![0; len];
+        reader.read_exact(&mut payload).await?;
+
+        let size = |payload: &[u8]| -> Result<(u16, u16)> {
+            anyhow::ensure!(
+                payload.len() == 4,
+                "Invalid terminal size in session message"
+            );
+            Ok((
+                u16::from_be_bytes([payload[0], payload[1]]),
+                u16::from_be_bytes([payload[2], payload[3]]),
+            ))
+        };
+        Ok(Some(match tag {
+            b'a' => {
+                let (rows, cols) = size(&payload)?;
+                Frame::Attach { rows, cols }
+            }
+            b'd' => Frame::Detach,
+            b'i' => Frame::Input(payload),
+            b'r' => {
+                let (rows, cols) = size(&payload)?;
+                Frame::Resize { rows, cols }
+            }
+            b'o' => Frame::Output(payload),
+            b'c' => Frame::Closed(String::from_utf8_lossy(&payload).into_owned()),
+            _ => anyhow::bail!("Unknown session message '{}'", tag as char),
+        }))
+    }
+}
+
+/// Session name from `--name` or the command's file name
+fn session_name(name: Option<&str>, command: &str) -> Result<String> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+    match name {
+        Some(name) => {
+            if name.is_empty() || name.starts_with('.') || !name.chars().all(valid) {
+                anyhow::bail!(
+                    "Invalid --name '{}': use letters, digits, '-', '_' and '.'",
+                    name
+                );
+            }
+            Ok(name.to_string())
+        }
+        None => {
+            let base = Path::new(command)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let name: String = base
+                .chars()
+                .map(|c| if valid(c) { c } else { '-' })
+                .collect();
+            let name = name.trim_start_matches('.');
+            Ok(if name.is_empty() { "supi" } else { name }.to_string())
+        }
+    }
+}
+
+/// The session's name when this process is the supervisor inside a detached session,
+/// which runs normally in the session's terminal. The variable is removed so it isn't
+/// passed on and the child could start sessions of its own.
+pub fn take_session_name() -> Option<String> {
+    let name = std::env::var(SESSION_ENV).ok();
+    std::env::remove_var(SESSION_ENV);
+    name
+}
+
+/// The session host's name when this process was started as one by `supi --detach`
+pub fn host_name() -> Option<String> {
+    std::env::var(HOST_ENV).ok()
+}
+
+/// Start this supi again in the background as a session host, which runs the
+/// supervisor in a pseudo terminal. Returns once the session accepts connections.
+pub fn detach(name: Option<&str>, command: &str, output: &Output) -> Result<()> {
+    let registry = Registry::open()?;
+    let name = session_name(name, command)?;
+    if let Some(session) = registry.sessions().iter().find(|s| s.name == name) {
+        anyhow::bail!(
+            "Session '{}' is already running (PID {}), pick another name with --name",
+            name,
+            session.pid
+        );
+    }
+
+    let mut host = std::process::Command::new(
+        std::env::current_exe().context("Failed to find the supi executable")?,
+    );
+    host.args(std::env::args_os().skip(1))
+        .env(HOST_ENV, &name)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped());
+    // SAFETY: setsid is async-signal-safe
+    unsafe {
+        host.pre_exec(|| {
+            nix::unistd::setsid()?;
+            Ok(())
+        });
+    }
+    let mut host = host.spawn().context("Failed to start the session host")?;
+
+    // The host reports errors on stderr and closes it once the session is up
+    let mut errors = String::new();
+    if let Some(mut stderr) = host.stderr.take() {
+        let _ = stderr.read_to_string(&mut errors);
+    }
+    // Errors mean the host is on its way out, wait so they aren't lost to the race
+    let status = match errors.trim().is_empty() {
+        true => host.try_wait()?,
+        false => Some(host.wait()?),
+    };
+    if let Some(status) = status {
+        anyhow::bail!(
+            "Failed to start session '{}' ({}): {}",
+            name,
+            status,
+            errors.trim()
+        );
+    }
+
+    let session = registry
+        .find(Some(&name))
+        .with_context(|| format!("Session '{}' didn't start", name))?;
+    output.log(&format!(
+        "[supi] Detached session '{}' (supervisor PID {})",
+        name, session.pid
+    ));
+    output.info(&format!(
+        "[supi] Attach with: supi attach {}, stop with: kill {}",
+        name, session.pid
+    ));
+    Ok(())
+}
+
+/// What client connections ask the host to do
+enum Request {
+    Input(Vec<u8>),
+    Resize(u16, u16),
+    DetachAll,
+}
+
+/// What the host tells attached clients
+#[derive(Clone)]
+enum Event {
+    Output(Arc<Vec<u8>>),
+    Close(String),
+}
+
+/// Run the session host: start the supervisor in a pseudo terminal, keep its
+/// recent output and serve `supi attach`/`supi detach` on the session socket.
+/// Returns the supervisor's exit code.
+pub async fn run_host(name: String) -> Result<i32> {
+    let registry = Registry::open()?;
+    let socket_path = registry.socket(&name);
+    let pidfile = registry.pidfile(&name);
+    // Only a socket nobody listens on is left over from a host that died
+    match tokio::net::UnixStream::connect(&socket_path).await {
+        Ok(_) => anyhow::bail!("Session '{}' is already running", name),
+        Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+            let _ = std::fs::remove_file(&socket_path);
+        }
+        Err(_) => {}
+    }
+    let listener = UnixListener::bind(&socket_path)
+        .with_context(|| format!("Failed to listen on {}", socket_path.display()))?;
+
+    let pty = nix::pty::openpty(Some(&winsize(DEFAULT_SIZE.0, DEFAULT_SIZE.1)), None)
+        .context("Failed to open a pseudo terminal")?;
+    let mut supervisor = spawn_supervisor(&name, pty.slave)?;
+    let pid = supervisor.id().unwrap_or(0);
+    std::fs::write(&pidfile, format!("{}\n", pid))
+        .with_context(|| format!("Failed to write {}", pidfile.display()))?;
+
+    // Tell `supi --detach` the session is up by closing the stderr it reads
+    let null = std::fs::OpenOptions::new().write(true).open("/dev/null")?;
+    // SAFETY: dup2 onto stderr only replaces that descriptor
+    nix::errno::Errno::result(unsafe { nix::libc::dup2(null.as_raw_fd(), 2) })?;
+
+    let master = std::fs::File::from(pty.master);
+    let mut pty_writer = master.try_clone()?;
+    let pty_fd = master.as_raw_fd();
+    let (output_tx, mut output_rx) = mpsc::channel::<Vec<u8>>(64);
+    // Reading the PTY blocks, and fails with EIO once the supervisor is gone
+    std::thread::spawn(move || {
+        let mut master = master;
+        let mut buf = [0u8; 8192];
+        while let Ok(n) = master.read(&mut buf) {
+            if n == 0 || output_tx.blocking_send(buf[..n].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (events, _) = broadcast::channel::<Event>(1024);
+    let (requests_tx, mut requests) = mpsc::unbounded_channel::<Request>();
+    let attached = Arc::new(AtomicUsize::new(0));
+    let mut replay: VecDeque<u8> = VecDeque::new();
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    let status = loop {
+        tokio::select! {
+            status = supervisor.wait() => break status,
+            chunk = output_rx.recv() => {
+                let Some(chunk) = chunk else {
+                    break supervisor.wait().await;
+                };
+                replay.extend(&chunk);
+                let excess = replay.len().saturating_sub(REPLAY_BYTES);
+                replay.drain(..excess);
+                let _ = events.send(Event::Output(Arc::new(chunk)));
+            }
+            Ok((stream, _)) = listener.accept() => {
+                // Subscribing and copying the replay in one step, so nothing is lost or doubled
+                let replay = start_at_line(replay.make_contiguous()).to_vec();
+                tokio::spawn(serve_client(
+                    stream,
+                    replay,
+                    events.subscribe(),
+                    requests_tx.clone(),
+                    attached.clone(),
+                ));
+            }
+            Some(request) = requests.recv() => match request {
+                Request::Input(bytes) => {
+                    let _ = pty_writer.write_all(&bytes);
+                }
+                Request::Resize(rows, cols) => {
+                    // SAFETY: TIOCSWINSZ only reads the winsize struct
+                    unsafe {
+                        nix::libc::ioctl(pty_fd, nix::libc::TIOCSWINSZ, &winsize(rows, cols));
+                    }
+                }
+                Request::DetachAll => {
+                    let _ = events.send(Event::Close(format!(
+                        "[supi] Detached from session '{}' by 'supi detach', it keeps running",
+                        name
+                    )));
+                }
+            },
+            // The session has no terminal to close, pass stop requests on
+            _ = terminate.recv() => forward_signal(pid, nix::sys::signal::Signal::SIGTERM),
+            _ = interrupt.recv() => forward_signal(pid, nix::sys::signal::Signal::SIGTERM),
+            _ = hangup.recv() => {}
+        }
+    };
+
+    // Pass on the last output, the PTY is read until it's drained or everything
+    // holding it is gone
+    while let Ok(Some(chunk)) =
+        tokio::time::timeout(tokio::time::Duration::from_millis(100), output_rx.recv()).await
+    {
+        let _ = events.send(Event::Output(Arc::new(chunk)));
+    }
+
+    let code = status.map(|status| status.code().unwrap_or(1)).unwrap_or(1);
+    let _ = std::fs::remove_file(&pidfile);
+    let _ = std::fs::remove_file(&socket_path);
+    let _ = events.send(Event::Close(format!(
+        "[supi] Session '{}' ended (supervisor exited with code {})",
+        name, code
+    )));
+    // Give attached clients a moment to receive the last output
+    tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    Ok(code)
+}
+
+/// Start the supervisor again, without --detach, with the PTY as its controlling terminal
+fn spawn_supervisor(name: &str, slave: OwnedFd) -> Result<tokio::process::Child> {
+    let mut command =
+        tokio::process::Command::new(std::env::current_exe().context("Failed to find supi")?);
+    command
+        .args(std::env::args_os().skip(1))
+        .env_remove(HOST_ENV)
+        .env(SESSION_ENV, name)
+        .stdin(slave.try_clone()?)
+        .stdout(slave.try_clone()?)
+        .stderr(slave);
+    // SAFETY: setsid and ioctl are async-signal-safe
+    unsafe {
+        command.pre_exec(|| {
+            nix::unistd::setsid()?;
+            nix::errno::Errno::result(nix::libc::ioctl(0, nix::libc::TIOCSCTTY as _, 0))?;
+            Ok(())
+        });
+    }
+    command
+        .spawn()
+        .context("Failed to start the supervisor in the session")
+}
+
+fn forward_signal(pid: u32, signal: nix::sys::signal::Signal) {
+    let _ = nix::sys::signal::kill(Pid::from_raw(pid as i32), signal);
+}
+
+fn winsize(rows: u16, cols: u16) -> nix::pty::Winsize {
+    nix::pty::Winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+/// Skip a partial first line, so replayed output doesn't start mid escape sequence
+fn start_at_line(replay: &[u8]) -> &[u8] {
+    if replay.len() < REPLAY_BYTES {
+        return replay;
+    }
+    match replay.iter().position(|b| *b == b'\n') {
+        Some(newline) => &replay[newline + 1..],
+        None => replay,
+    }
+}
+
+/// Handle one connection: either a terminal attaching or `supi detach`
+async fn serve_client(
+    stream: UnixStream,
+    replay: Vec<u8>,
+    mut events: broadcast::Receiver<Event>,
+    requests: mpsc::UnboundedSender<Request>,
+    attached: Arc<AtomicUsize>,
+) {
+    let (mut reader, mut writer) = stream.into_split();
+
+    match Frame::read_from(&mut reader).await {
+        Ok(Some(Frame::Attach { rows, cols })) => {
+            let _ = requests.send(Request::Resize(rows, cols));
+        }
+        Ok(Some(Frame::Detach)) => {
+            let count = attached.load(Ordering::SeqCst);
+            let _ = requests.send(Request::DetachAll);
+            let _ = Frame::Closed(count.to_string()).write_to(&mut writer).await;
+            return;
+        }
+        _ => return,
+    }
+
+    attached.fetch_add(1, Ordering::SeqCst);
+
+    // Frames are read in their own task, a partly read frame can't be dropped by select!
+    let mut input = tokio::spawn(async move {
+        while let Ok(Some(frame)) = Frame::read_from(&mut reader).await {
+            let request = match frame {
+                Frame::Input(bytes) => Request::Input(bytes),
+                Frame::Resize { rows, cols } => Request::Resize(rows, cols),
+                _ => continue,
+            };
+            if requests.send(request).is_err() {
+                break;
+            }
+        }
+    });
+
+    if Frame::Output(replay).write_to(&mut writer).await.is_ok() {
+        loop {
+            let close = tokio::select! {
+                _ = &mut input => break,
+                event = events.recv() => match event {
+                    Ok(Event::Output(bytes)) => {
+                        if Frame::Output(bytes.to_vec()).write_to(&mut writer).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                    Ok(Event::Close(reason)) => reason,
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        "[supi] Detached, the terminal couldn't keep up with the output".to_string()
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+            let _ = Frame::Closed(close).write_to(&mut writer).await;
+            break;
+        }
+    }
+    input.abort();
+    attached.fetch_sub(1, Ordering::SeqCst);
+}
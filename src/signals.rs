@@ -1,54 +1,297 @@
+use crate::action::Action;
+use crate::error::Error;
+use crate::reaper::INIT_FORWARD_SIGNALS;
 use anyhow::{Context, Result};
+use nix::sys::signal::Signal;
 use signal_hook::consts::signal::*;
 use signal_hook_tokio::Signals;
+use std::fmt;
+use std::str::FromStr;
 use tokio_stream::StreamExt;
 
-pub enum SignalEvent {
-    Terminate(String), // SIGINT, SIGTERM, SIGQUIT with signal name
-    Restart(String),   // Configurable restart signal (e.g., SIGUSR1) with signal name
+/// What supi does when it receives a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalAction {
+    /// Stop the child and exit supi
+    Terminate,
+    /// Send the same signal to the child process group
+    Forward,
+    /// Catch the signal and do nothing
+    Ignore,
+    /// Run a supervisor action, e.g. restart or dump
+    Run(Action),
+}
+
+impl SignalAction {
+    /// Parse an action name as used in `--on-signal SIGNAL=action`
+    pub fn parse(name: &str) -> crate::Result<Self> {
+        let action = match name.trim().to_lowercase().as_str() {
+            "terminate" | "quit" | "exit" => SignalAction::Terminate,
+            "forward" => SignalAction::Forward,
+            "ignore" => SignalAction::Ignore,
+            _ => {
+                let invalid = |reason: String| Error::InvalidValue {
+                    kind: "signal action",
+                    value: name.to_string(),
+                    reason,
+                };
+                match Action::parse(name) {
+                    Ok(Action::Palette) => {
+                        return Err(invalid(
+                            "the palette can't be opened by a signal".to_string(),
+                        ))
+                    }
+                    Ok(action) => SignalAction::Run(action),
+                    Err(_) => {
+                        return Err(invalid(format!(
+                            "expected one of terminate, forward, ignore, {}",
+                            Action::ALL
+                                .iter()
+                                .filter(|action| **action != Action::Palette)
+                                .map(|action| action.name())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )))
+                    }
+                }
+            }
+        };
+        Ok(action)
+    }
+}
+
+impl fmt::Display for SignalAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignalAction::Terminate => write!(f, "terminate"),
+            SignalAction::Forward => write!(f, "forward"),
+            SignalAction::Ignore => write!(f, "ignore"),
+            SignalAction::Run(action) => write!(f, "{}", action),
+        }
+    }
+}
+
+/// A received signal together with the action mapped to it
+#[derive(Debug, Clone)]
+pub struct SignalEvent {
+    pub signal: i32,
+    pub name: String,
+    pub action: SignalAction,
 }
 
 pub struct SignalHandler {
     signals: Signals,
+    mappings: Vec<(i32, SignalAction)>,
 }
 
 impl SignalHandler {
-    pub fn new(restart_signal_name: &str) -> Result<Self> {
-        let restart_signal = parse_signal_name(restart_signal_name)
-            .context(format!("Invalid restart signal: {}", restart_signal_name))?;
+    /// Set up signal handling. SIGINT, SIGTERM and SIGQUIT terminate supi, in init mode
+    /// SIGHUP, SIGUSR2 and SIGWINCH are forwarded, the restart and dump signals run their
+    /// actions, `--forward-signal` signals are relayed to the child, and
+    /// `--on-signal SIGNAL[,SIGNAL...]=action` mappings are applied last, overriding
+    /// earlier ones for the same signal.
+    pub fn new(
+        restart_signal_name: &str,
+        dump_signal_name: Option<&str>,
+        forward_signals: &[String],
+        on_signal: &[String],
+        init_mode: bool,
+    ) -> crate::Result<Self> {
+        let mappings = signal_mappings(
+            restart_signal_name,
+            dump_signal_name,
+            forward_signals,
+            on_signal,
+            init_mode,
+        )?;
 
-        // Set up signal handlers for termination signals and restart signal
-        let signals = Signals::new(&[SIGINT, SIGTERM, SIGQUIT, restart_signal])
-            .context("Failed to create signal handler")?;
+        let signals = Signals::new(mappings.iter().map(|(signal, _)| *signal))
+            .map_err(|e| Error::setup("signal handling", e))?;
 
-        Ok(Self { signals })
+        Ok(Self { signals, mappings })
     }
 
+    /// Wait for the next signal and return the action mapped to it
     pub async fn next(&mut self) -> Option<SignalEvent> {
-        if let Some(signal) = self.signals.next().await {
-            match signal {
-                SIGINT => Some(SignalEvent::Terminate("SIGINT".to_string())),
-                SIGTERM => Some(SignalEvent::Terminate("SIGTERM".to_string())),
-                SIGQUIT => Some(SignalEvent::Terminate("SIGQUIT".to_string())),
-                SIGUSR1 => Some(SignalEvent::Restart("SIGUSR1".to_string())),
-                SIGUSR2 => Some(SignalEvent::Restart("SIGUSR2".to_string())),
-                SIGHUP => Some(SignalEvent::Restart("SIGHUP".to_string())),
-                _ => None,
+        while let Some(signal) = self.signals.next().await {
+            if let Some((_, action)) = self.mappings.iter().find(|(s, _)| *s == signal) {
+                return Some(SignalEvent {
+                    signal,
+                    name: signal_name(signal),
+                    action: *action,
+                });
             }
-        } else {
-            None
         }
+        None
     }
+
+    /// Human-readable list of all handled signals, e.g. `SIGINT=terminate, SIGUSR1=restart`
+    pub fn describe(&self) -> String {
+        self.mappings
+            .iter()
+            .map(|(signal, action)| format!("{}={}", signal_name(*signal), action))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn set_mapping(mappings: &mut Vec<(i32, SignalAction)>, signal: i32, action: SignalAction) {
+    mappings.retain(|(s, _)| *s != signal);
+    mappings.push((signal, action));
 }
 
-fn parse_signal_name(name: &str) -> Result<i32> {
-    match name.to_uppercase().as_str() {
-        "SIGUSR1" | "USR1" => Ok(SIGUSR1),
-        "SIGUSR2" | "USR2" => Ok(SIGUSR2),
-        "SIGHUP" | "HUP" => Ok(SIGHUP),
-        _ => anyhow::bail!(
-            "Unsupported signal: {}. Supported: SIGUSR1, SIGUSR2, SIGHUP",
-            name
-        ),
+/// The signal to action mappings for [`SignalHandler::new`], later ones win
+fn signal_mappings(
+    restart_signal_name: &str,
+    dump_signal_name: Option<&str>,
+    forward_signals: &[String],
+    on_signal: &[String],
+    init_mode: bool,
+) -> crate::Result<Vec<(i32, SignalAction)>> {
+    let restart_signal = parse_catchable_signal(restart_signal_name)
+        .map_err(|e| Error::invalid_option("restart_signal", restart_signal_name, e))?;
+
+    let mut mappings = vec![
+        (SIGINT, SignalAction::Terminate),
+        (SIGTERM, SignalAction::Terminate),
+        (SIGQUIT, SignalAction::Terminate),
+    ];
+    if init_mode {
+        for signal in INIT_FORWARD_SIGNALS {
+            set_mapping(&mut mappings, signal, SignalAction::Forward);
+        }
+    }
+    set_mapping(
+        &mut mappings,
+        restart_signal,
+        SignalAction::Run(Action::Restart),
+    );
+
+    if let Some(name) = dump_signal_name {
+        let invalid = |source: crate::Source| Error::invalid_option("dump_signal", name, source);
+        let signal = parse_catchable_signal(name).map_err(|e| invalid(e.into()))?;
+        if signal == restart_signal {
+            return Err(invalid(
+                format!("must differ from restart signal {}", restart_signal_name).into(),
+            ));
+        }
+        set_mapping(&mut mappings, signal, SignalAction::Run(Action::Dump));
+    }
+
+    for name in forward_signals {
+        let signal = parse_catchable_signal(name)
+            .map_err(|e| Error::invalid_option("forward_signals", name, e))?;
+        set_mapping(&mut mappings, signal, SignalAction::Forward);
     }
+
+    for spec in on_signal {
+        let invalid = |source: crate::Source| Error::invalid_option("on_signals", spec, source);
+        let (names, action) = spec
+            .split_once('=')
+            .ok_or_else(|| invalid("expected SIGNAL[,SIGNAL...]=ACTION".into()))?;
+        let action = SignalAction::parse(action).map_err(|e| invalid(e.into()))?;
+        for name in names.split(',') {
+            let signal = parse_catchable_signal(name).map_err(|e| invalid(e.into()))?;
+            set_mapping(&mut mappings, signal, action);
+        }
+    }
+
+    Ok(mappings)
+}
+
+/// Parse a signal that supi is allowed to handle
+fn parse_catchable_signal(name: &str) -> Result<i32> {
+    let signal = parse_signal(name)?;
+    if signal_hook::consts::FORBIDDEN.contains(&signal) {
+        anyhow::bail!("Signal {} can't be caught", signal_name(signal));
+    }
+    Ok(signal)
+}
+
+/// Name of a signal number, e.g. `SIGHUP` or `SIGRTMIN+3`
+pub fn signal_name(signal: i32) -> String {
+    if let Ok(sig) = Signal::try_from(signal) {
+        return sig.as_str().to_string();
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let (rtmin, rtmax) = (nix::libc::SIGRTMIN(), nix::libc::SIGRTMAX());
+        if signal == rtmax {
+            return "SIGRTMAX".to_string();
+        }
+        if (rtmin..rtmax).contains(&signal) {
+            return match signal - rtmin {
+                0 => "SIGRTMIN".to_string(),
+                offset => format!("SIGRTMIN+{}", offset),
+            };
+        }
+    }
+
+    format!("signal {}", signal)
+}
+
+/// Names of the signals supi can catch, for shell completion
+pub fn signal_names() -> Vec<String> {
+    let mut names: Vec<String> = Signal::iterator()
+        .filter(|sig| !matches!(sig, Signal::SIGKILL | Signal::SIGSTOP))
+        .map(|sig| sig.as_str().to_string())
+        .collect();
+    #[cfg(target_os = "linux")]
+    names.extend(["SIGRTMIN".to_string(), "SIGRTMAX".to_string()]);
+    names
+}
+
+/// Parse a signal by name (`HUP`, `SIGHUP`), realtime name (`SIGRTMIN+3`, `SIGRTMAX-1`)
+/// or number
+pub fn parse_signal(name: &str) -> Result<i32> {
+    let upper = name.trim().to_uppercase();
+    let bare = upper.strip_prefix("SIG").unwrap_or(&upper);
+
+    if let Ok(number) = bare.parse::<i32>() {
+        if signal_name(number).starts_with("signal ") {
+            anyhow::bail!("Unknown signal number: {}", number);
+        }
+        return Ok(number);
+    }
+
+    if bare.starts_with("RTMIN") || bare.starts_with("RTMAX") {
+        return parse_realtime_signal(bare).with_context(|| format!("Invalid signal: {}", name));
+    }
+
+    Signal::from_str(&format!("SIG{}", bare))
+        .map(|signal| signal as i32)
+        .map_err(|_| anyhow::anyhow!("Unknown signal: {}", name.trim()))
+}
+
+#[cfg(target_os = "linux")]
+fn parse_realtime_signal(bare: &str) -> Result<i32> {
+    let (rtmin, rtmax) = (nix::libc::SIGRTMIN(), nix::libc::SIGRTMAX());
+
+    let offset = |rest: &str, sign: char| -> Result<i32> {
+        match rest.strip_prefix(sign) {
+            Some(n) => n
+                .parse::<i32>()
+                .with_context(|| format!("'{}' is not a number", n)),
+            None if rest.is_empty() => Ok(0),
+            None => anyhow::bail!("expected RTMIN+<n> or RTMAX-<n>"),
+        }
+    };
+
+    let signal = match bare.strip_prefix("RTMIN") {
+        Some(rest) => rtmin.checked_add(offset(rest, '+')?),
+        None => rtmax.checked_sub(offset(&bare["RTMAX".len()..], '-')?),
+    };
+    let Some(signal) = signal.filter(|signal| (rtmin..=rtmax).contains(signal)) else {
+        anyhow::bail!(
+            "realtime signals range from SIGRTMIN to SIGRTMIN+{}",
+            rtmax - rtmin
+        );
+    };
+    Ok(signal)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn parse_realtime_signal(_bare: &str) -> Result<i32> {
+    anyhow::bail!("realtime signals are only supported on Linux")
 }
@@ -1,46 +1,769 @@
-use crate::hotkey::HotkeyListener;
+use crate::action::Action;
+use crate::buffer::{OutputBuffer, Stream};
+use crate::error::{Error, Result};
+use crate::events::{RestartSource, StopReason, SupervisorEvent};
+use crate::filter::OutputFilter;
+use crate::handle::{Request, Status, SupervisorHandle};
+use crate::hotkey::{HotkeyListener, Keymap};
 use crate::output::Output;
+use crate::palette::Command;
 use crate::process::ProcessManager;
-use crate::signals::{SignalEvent, SignalHandler};
-use anyhow::Result;
-use tokio::time::Instant;
+use crate::reaper::{OwnChildren, Reaper};
+use crate::schedule::RestartSchedule;
+use crate::signals::{signal_name, SignalAction, SignalEvent, SignalHandler};
+use crate::triggers::{TriggerAction, TriggerMatch};
+use crate::units::format_duration;
+use crate::watchdog::Watchdog;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{interval, Duration, Instant};
 
+/// How often the number of lines hidden by output filters is reported
+const FILTER_REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Exit code when a run exceeds --max-runtime with --stop-on-child-exit, as timeout(1) uses
+const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Runs the child and reacts to signals, hotkeys, triggers and schedules until it's
+/// time to exit. Built with [`SupervisorBuilder`](crate::SupervisorBuilder).
 pub struct Supervisor {
     process_manager: ProcessManager,
     signal_handler: SignalHandler,
+    hotkeys: bool,
     hotkey_listener: Option<HotkeyListener>,
     stop_on_child_exit: bool,
     restart_signal: String,
-    restart_hotkey: char,
+    keymap: Keymap,
     output: Output,
     debounce_ms: u64,
     last_restart: Option<Instant>,
+    buffer: OutputBuffer,
+    dump_file: PathBuf,
+    crash_summary_lines: usize,
+    trigger_matches: mpsc::UnboundedReceiver<TriggerMatch>,
+    filter: OutputFilter,
+    filter_reported: u64,
+    status_line_shown: bool,
+    init_mode: bool,
+    reaper: Option<Reaper>,
+    own_children: OwnChildren,
+    watchdog: Option<Watchdog>,
+    max_runtime: Option<Duration>,
+    schedule: Option<RestartSchedule>,
+    autostart: bool,
+    start_delay: Option<Duration>,
+    requests: mpsc::UnboundedReceiver<Request>,
+    request_sender: mpsc::UnboundedSender<Request>,
 }
 
 impl Supervisor {
-    pub fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
         process_manager: ProcessManager,
         signal_handler: SignalHandler,
-        hotkey_listener: Option<HotkeyListener>,
+        hotkeys: bool,
         stop_on_child_exit: bool,
         restart_signal: String,
-        restart_hotkey: char,
+        keymap: Keymap,
         output: Output,
         debounce_ms: u64,
+        buffer: OutputBuffer,
+        dump_file: PathBuf,
+        crash_summary_lines: usize,
+        trigger_matches: mpsc::UnboundedReceiver<TriggerMatch>,
+        filter: OutputFilter,
+        init_mode: bool,
+        watchdog: Option<Watchdog>,
+        max_runtime: Option<Duration>,
+        schedule: Option<RestartSchedule>,
+        autostart: bool,
+        start_delay: Option<Duration>,
     ) -> Self {
+        let (request_sender, requests) = mpsc::unbounded_channel();
         Self {
             process_manager,
             signal_handler,
-            hotkey_listener,
+            hotkeys,
+            hotkey_listener: None,
             stop_on_child_exit,
             restart_signal,
-            restart_hotkey,
+            keymap,
             output,
             debounce_ms,
             last_restart: None,
+            buffer,
+            dump_file,
+            crash_summary_lines,
+            trigger_matches,
+            filter,
+            filter_reported: 0,
+            status_line_shown: false,
+            init_mode,
+            reaper: None,
+            own_children: OwnChildren::default(),
+            watchdog,
+            max_runtime,
+            schedule,
+            autostart,
+            start_delay,
+            requests,
+            request_sender,
+        }
+    }
+
+    /// A handle to restart, stop or query the supervisor from other tasks while it runs
+    pub fn handle(&self) -> SupervisorHandle {
+        SupervisorHandle::new(
+            self.request_sender.clone(),
+            self.process_manager.events().clone(),
+        )
+    }
+
+    /// Receive lifecycle events and child output from now on, see [`SupervisorEvent`]
+    pub fn subscribe(&self) -> broadcast::Receiver<SupervisorEvent> {
+        self.process_manager.subscribe()
+    }
+
+    /// Check that the command exists and is executable, without starting it
+    pub fn check_command(&self) -> Result<()> {
+        self.process_manager.check_command()
+    }
+
+    /// Restart the child if it's running, otherwise start it
+    async fn restart_or_start(&mut self) -> Result<()> {
+        if self.process_manager.is_running() {
+            self.report_hidden_lines_for_run();
+            self.process_manager.restart().await
+        } else {
+            self.output
+                .log("[supi] Child process not running, starting...");
+            self.process_manager.spawn().await
+        }
+    }
+
+    /// Write the output buffer to the dump file
+    fn dump_buffer(&self) {
+        if !self.buffer.is_enabled() {
+            self.output
+                .elog("[supi] Output buffer is disabled (--buffer-lines 0), nothing to dump");
+            return;
+        }
+
+        match self.buffer.dump_to_file(&self.dump_file) {
+            Ok(count) => self.output.log(&format!(
+                "[supi] Dumped {} buffered lines to {}",
+                count,
+                self.dump_file.display()
+            )),
+            Err(e) => self
+                .output
+                .elog(&format!("[supi] Failed to dump output buffer: {:#}", e)),
+        }
+    }
+
+    /// Turn output filters on or off
+    fn toggle_filter(&self) {
+        if !self.filter.has_rules() {
+            self.output
+                .log("[supi] No output filters configured (--hide / --only)");
+        } else if self.filter.toggle() {
+            self.output.log("[supi] Output filters enabled");
+        } else {
+            match self.keymap.key_for(Action::ToggleFilter) {
+                Some(key) => self.output.log(&format!(
+                    "[supi] Output filters disabled, press '{}' to re-enable",
+                    key
+                )),
+                None => self.output.log("[supi] Output filters disabled"),
+            }
+        }
+    }
+
+    /// Periodically report how many lines the filters hid since the last report
+    fn report_hidden_lines(&mut self) {
+        let total = self.filter.hidden_total();
+        if total > self.filter_reported {
+            self.output.log(&format!(
+                "[supi] {} lines hidden by output filters ({} total)",
+                total - self.filter_reported,
+                total
+            ));
+            self.filter_reported = total;
+        }
+    }
+
+    /// Report how many lines the filters hid during the run that just ended
+    fn report_hidden_lines_for_run(&self) {
+        let hidden = self.filter.take_hidden_run();
+        if hidden > 0 {
+            self.output.log(&format!(
+                "[supi] {} lines hidden by output filters during run #{}",
+                hidden,
+                self.process_manager.run()
+            ));
+        }
+    }
+
+    /// Run the action of a matched output trigger.
+    /// Returns `Some(exit_code)` when the supervisor should exit.
+    async fn handle_trigger(&mut self, trigger: TriggerMatch) -> Result<Option<i32>> {
+        self.output.log(&format!(
+            "[supi] Trigger /{}/ matched on {}: {}",
+            trigger.pattern, trigger.stream, trigger.action
+        ));
+
+        match trigger.action {
+            TriggerAction::Restart => {
+                self.request_restart(RestartSource::Trigger(trigger.pattern.clone()));
+                if self.should_allow_restart() {
+                    self.restart_or_start().await?;
+                }
+            }
+            TriggerAction::Stop => {
+                if self.process_manager.is_running() {
+                    self.report_hidden_lines_for_run();
+                    self.process_manager.shutdown().await?;
+                    self.print_restart_hint();
+                }
+            }
+            TriggerAction::Exit(code) => {
+                if self.process_manager.is_running() {
+                    self.report_hidden_lines_for_run();
+                }
+                self.process_manager.shutdown().await?;
+                self.output
+                    .log(&format!("[supi] Exiting with code {} (trigger)", code));
+                return Ok(Some(code));
+            }
+            TriggerAction::Run(command) => {
+                // Spawned here rather than in the task, so the PID is registered before
+                // the reaper (which runs in this loop) can see the command exit
+                let spawned = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(&command)
+                    .env("SUPI_TRIGGER_LINE", &trigger.line)
+                    .stdin(std::process::Stdio::null())
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::null())
+                    .spawn();
+                let pid = spawned.as_ref().ok().and_then(|child| child.id());
+                if let Some(pid) = pid {
+                    self.own_children.insert(pid);
+                }
+
+                let output = self.output.clone();
+                let own_children = self.own_children.clone();
+                tokio::spawn(async move {
+                    let status = match spawned {
+                        Ok(mut child) => child.wait().await,
+                        Err(e) => Err(e),
+                    };
+                    if let Some(pid) = pid {
+                        own_children.remove(pid);
+                    }
+                    match status {
+                        Ok(status) if status.success() => {}
+                        Ok(status) => output.elog(&format!(
+                            "[supi] Trigger command '{}' exited with status: {}",
+                            command, status
+                        )),
+                        Err(e) => output.elog(&format!(
+                            "[supi] Failed to run trigger command '{}': {}",
+                            command, e
+                        )),
+                    }
+                });
+            }
+            TriggerAction::Notify => {
+                self.output.banner(&trigger.line);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Handle an action triggered by a hotkey, the command palette or a signal.
+    /// Returns true when the supervisor should exit.
+    async fn handle_action(&mut self, action: Action, source: RestartSource) -> Result<bool> {
+        match action {
+            Action::Restart => {
+                self.output.log("[supi] Hotkey pressed, restarting...");
+                self.request_restart(source);
+
+                if self.should_allow_restart() {
+                    self.restart_or_start().await?;
+                }
+            }
+            Action::Stop => {
+                if self.process_manager.is_running() {
+                    self.report_hidden_lines_for_run();
+                    self.process_manager.shutdown().await?;
+                    self.print_restart_hint();
+                } else {
+                    self.output.log("[supi] Child process not running");
+                }
+            }
+            Action::Start => {
+                if self.process_manager.is_running() {
+                    self.output.log("[supi] Child process already running");
+                } else {
+                    self.process_manager.spawn().await?;
+                }
+            }
+            Action::Kill => {
+                if self.process_manager.is_running() {
+                    self.report_hidden_lines_for_run();
+                    self.process_manager.kill().await?;
+                    self.print_restart_hint();
+                } else {
+                    self.output.log("[supi] Child process not running");
+                }
+            }
+            Action::Quit => {
+                let reason = match source {
+                    RestartSource::Signal(name) => format!("Received {} (quit)", name),
+                    _ => "Quit requested".to_string(),
+                };
+                self.output
+                    .log(&format!("[supi] {}, shutting down...", reason));
+                self.process_manager.shutdown().await?;
+                return Ok(true);
+            }
+            Action::Clear => self.output.clear_screen(),
+            Action::Status => self.print_status(),
+            Action::Pause => {
+                let paused = !self.process_manager.is_paused();
+                self.set_paused(paused);
+            }
+            Action::Dump => {
+                self.output
+                    .log("[supi] Dump hotkey pressed, dumping output buffer...");
+                self.dump_buffer();
+            }
+            Action::ToggleFilter => self.toggle_filter(),
+            Action::Help => self.print_help(),
+            // The palette is opened by the hotkey listener itself
+            Action::Palette => {}
+        }
+
+        Ok(false)
+    }
+
+    /// Handle a received signal according to its mapped action.
+    /// Returns true when the supervisor should exit.
+    async fn handle_signal(&mut self, event: SignalEvent) -> Result<bool> {
+        match event.action {
+            SignalAction::Terminate => {
+                self.output.log(&format!(
+                    "[supi] Received {} signal, shutting down...",
+                    event.name
+                ));
+                self.process_manager.shutdown().await?;
+                return Ok(true);
+            }
+            SignalAction::Forward => self.forward_signal(&event),
+            SignalAction::Ignore => self
+                .output
+                .log(&format!("[supi] Received {} signal (ignored)", event.name)),
+            SignalAction::Run(Action::Restart) => {
+                self.output
+                    .log(&format!("[supi] Received {} signal", event.name));
+                self.request_restart(RestartSource::Signal(event.name.clone()));
+
+                // Skip restart due to debounce
+                if self.should_allow_restart() {
+                    self.restart_or_start().await?;
+                }
+            }
+            SignalAction::Run(Action::Dump) => {
+                self.output.log(&format!(
+                    "[supi] Received {} signal, dumping output buffer...",
+                    event.name
+                ));
+                self.dump_buffer();
+            }
+            SignalAction::Run(action) => {
+                self.output.log(&format!(
+                    "[supi] Received {} signal: {}",
+                    event.name, action
+                ));
+                return self
+                    .handle_action(action, RestartSource::Signal(event.name))
+                    .await;
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Relay a received signal to the child process group
+    fn forward_signal(&self, event: &SignalEvent) {
+        let Some(pid) = self.process_manager.pid() else {
+            self.output.log(&format!(
+                "[supi] Received {} signal, child process not running, nothing to forward",
+                event.name
+            ));
+            return;
+        };
+
+        match self.process_manager.send_signal(event.signal) {
+            Ok(()) => self.output.log(&format!(
+                "[supi] Received {} signal, forwarded to child process group {}",
+                event.name, pid
+            )),
+            Err(e) => self.output.elog(&format!(
+                "[supi] Received {} signal, not forwarded: {:#}",
+                event.name, e
+            )),
+        }
+    }
+
+    /// Handle a hotkey action or a command typed into the command palette.
+    /// Returns true when the supervisor should exit.
+    async fn handle_command(&mut self, command: Command) -> Result<bool> {
+        match command {
+            Command::Action(action) => {
+                return self.handle_action(action, RestartSource::Hotkey).await
+            }
+            Command::Signal(signal) => match self.process_manager.send_signal(signal) {
+                Ok(()) => self.output.log(&format!(
+                    "[supi] Sent {} to child process group",
+                    signal_name(signal)
+                )),
+                Err(e) => self.output.elog(&format!("[supi] {:#}", e)),
+            },
+            Command::EnvSet(key, value) => {
+                self.output.log(&format!(
+                    "[supi] Set {}={} (applies from the next restart)",
+                    key, value
+                ));
+                self.process_manager.set_env(key, value);
+            }
+            Command::EnvUnset(key) => {
+                self.output.log(&format!(
+                    "[supi] Unset {} (applies from the next restart)",
+                    key
+                ));
+                self.process_manager.unset_env(key);
+            }
+            Command::EnvList => {
+                let overrides = self.process_manager.env_overrides();
+                if overrides.is_empty() {
+                    self.output
+                        .info("[supi] No environment changes made at runtime");
+                }
+                for (key, value) in overrides {
+                    match value {
+                        Some(value) => self.output.info(&format!("[supi] {}={}", key, value)),
+                        None => self.output.info(&format!("[supi] {} (unset)", key)),
+                    }
+                }
+            }
+            Command::Pause => self.set_paused(true),
+            Command::Resume => self.set_paused(false),
+            Command::Debounce(ms) => {
+                self.debounce_ms = ms;
+                if ms == 0 {
+                    self.output.log("[supi] Restart debounce disabled");
+                } else {
+                    self.output
+                        .log(&format!("[supi] Restart debounce set to {}ms", ms));
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Pause (SIGSTOP) or resume (SIGCONT) the child process group
+    fn set_paused(&mut self, paused: bool) {
+        match self.process_manager.set_paused(paused) {
+            Ok(true) if paused => self
+                .output
+                .log("[supi] Child process group paused (SIGSTOP)"),
+            Ok(true) => self
+                .output
+                .log("[supi] Child process group resumed (SIGCONT)"),
+            Ok(false) if paused => self.output.log("[supi] Child process is already paused"),
+            Ok(false) => self.output.log("[supi] Child process is not paused"),
+            Err(e) => self.output.elog(&format!(
+                "[supi] Cannot {}: {:#}",
+                if paused { "pause" } else { "resume" },
+                e
+            )),
+        }
+    }
+
+    /// Show a sticky status line while the child is paused, remove it otherwise
+    fn update_status_line(&mut self) {
+        let paused = self.process_manager.is_paused();
+        if paused == self.status_line_shown {
+            return;
+        }
+
+        if paused {
+            let hint = match self.keymap.key_for(Action::Pause) {
+                Some(key) => format!("press '{}' or type :resume to continue", key),
+                None => "type :resume to continue".to_string(),
+            };
+            self.output.set_status(Some(&format!(
+                "PAUSED: child process group stopped (PID {}), {}",
+                self.process_manager.pid().unwrap_or_default(),
+                hint
+            )));
+        } else {
+            self.output.set_status(None);
+        }
+        self.status_line_shown = paused;
+    }
+
+    /// Print the current child state
+    fn print_status(&self) {
+        match self.process_manager.pid() {
+            Some(pid) => self.output.info(&format!(
+                "[supi] Status: {} (PID: {}, run #{}{})",
+                if self.process_manager.is_paused() {
+                    "paused"
+                } else {
+                    "running"
+                },
+                pid,
+                self.process_manager.run(),
+                self.watchdog
+                    .as_ref()
+                    .and_then(|watchdog| watchdog.usage(pid))
+                    .map(|usage| format!(", {}", usage))
+                    .unwrap_or_default()
+            )),
+            None if self.process_manager.run() == 0 => {
+                self.output.info("[supi] Status: not started")
+            }
+            None => self.output.info(&format!(
+                "[supi] Status: stopped (last run #{})",
+                self.process_manager.run()
+            )),
+        }
+    }
+
+    /// The current state of the child, for `SupervisorHandle::status`
+    fn status(&self) -> Status {
+        Status {
+            pid: self.process_manager.pid(),
+            run: self.process_manager.run(),
+            paused: self.process_manager.is_paused(),
+            uptime: self.process_manager.uptime(),
+            last_exit_code: self.process_manager.last_exit_code(),
+        }
+    }
+
+    /// Answer a request from a `SupervisorHandle`.
+    /// Returns true when the supervisor should exit.
+    async fn handle_request(&mut self, request: Request) -> Result<bool> {
+        match request {
+            Request::Restart(reply) => {
+                self.output.log("[supi] Restart requested, restarting...");
+                self.request_restart(RestartSource::Request);
+                if self.should_allow_restart() {
+                    self.restart_or_start().await?;
+                }
+                let _ = reply.send(());
+            }
+            Request::Stop(reply) => {
+                self.output.log("[supi] Stop requested, shutting down...");
+                self.process_manager.shutdown().await?;
+                let _ = reply.send(());
+                return Ok(true);
+            }
+            Request::Status(reply) => {
+                let _ = reply.send(self.status());
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Start reading hotkeys, unless they are off or there is no terminal to read them from
+    fn start_hotkey_listener(&mut self) {
+        if !self.hotkeys {
+            return;
+        }
+        // Containers often run without a terminal, there is nothing to read keys from then.
+        if !std::io::stdin().is_terminal() {
+            self.output
+                .log("[supi] stdin is not a terminal, hotkeys disabled");
+            return;
+        }
+        match HotkeyListener::new(self.keymap.clone(), self.output.clone()) {
+            Ok(listener) => self.hotkey_listener = Some(listener),
+            Err(e) => {
+                self.output.elog(&format!(
+                    "[supi] Warning: Could not set up hotkey listener: {}",
+                    e
+                ));
+                self.output
+                    .elog("[supi] Continuing without hotkey support (signals still work)");
+            }
         }
     }
 
+    /// Print all key bindings together with the live supervisor state
+    fn print_help(&self) {
+        let mut lines = vec!["Key bindings:".to_string()];
+        let key_width = self
+            .keymap
+            .bindings()
+            .iter()
+            .map(|(key, _)| key.to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max("ctrl+c".len());
+        for (key, action) in self.keymap.bindings() {
+            lines.push(format!(
+                "  {:<width$}  {}",
+                key.to_string(),
+                action,
+                width = key_width
+            ));
+        }
+        lines.push(format!(
+            "  {:<width$}  exit supi",
+            "ctrl+c",
+            width = key_width
+        ));
+        lines.push(String::new());
+
+        lines.push(format!("Restart signal: {}", self.restart_signal));
+        lines.push(format!("Signals: {}", self.signal_handler.describe()));
+        match (self.process_manager.pid(), self.process_manager.uptime()) {
+            (Some(pid), Some(uptime)) => {
+                let paused = if self.process_manager.is_paused() {
+                    " (paused)"
+                } else {
+                    ""
+                };
+                lines.push(format!("Child: running{}, PID {}", paused, pid));
+                lines.push(format!("Uptime: {}", format_duration(uptime)));
+                if let Some(usage) = self.watchdog.as_ref().and_then(|w| w.usage(pid)) {
+                    lines.push(format!("Resources: {}", usage));
+                }
+            }
+            _ if self.process_manager.run() == 0 => lines.push("Child: not started".to_string()),
+            _ => lines.push("Child: stopped".to_string()),
+        }
+        lines.push(format!(
+            "Restarts: {}",
+            self.process_manager.run().saturating_sub(1)
+        ));
+        if let Some(max_runtime) = self.max_runtime {
+            match self.process_manager.uptime() {
+                Some(uptime) => lines.push(format!(
+                    "Max runtime: {} ({} left)",
+                    format_duration(max_runtime),
+                    format_duration(max_runtime.saturating_sub(uptime))
+                )),
+                None => lines.push(format!("Max runtime: {}", format_duration(max_runtime))),
+            }
+        }
+
+        if let Some(schedule) = &self.schedule {
+            lines.push(format!(
+                "Scheduled restart: {}, next at {}",
+                schedule.describe(),
+                schedule.describe_next()
+            ));
+        }
+
+        let debounce = if self.debounce_ms == 0 {
+            "disabled".to_string()
+        } else {
+            let window = Duration::from_millis(self.debounce_ms);
+            let remaining = self
+                .last_restart
+                .map(|last| window.saturating_sub(last.elapsed()))
+                .unwrap_or_default();
+            if remaining.is_zero() {
+                format!("{} (ready)", format_duration(window))
+            } else {
+                format!(
+                    "{} ({} left)",
+                    format_duration(window),
+                    format_duration(remaining)
+                )
+            }
+        };
+        lines.push(format!("Debounce window: {}", debounce));
+
+        self.output.panel("supi help", &lines);
+    }
+
+    /// Tell the user how to bring the stopped child back
+    fn print_restart_hint(&self) {
+        let restart_key = self
+            .hotkey_listener
+            .as_ref()
+            .and(self.keymap.key_for(Action::Restart));
+        if let Some(key) = restart_key {
+            self.output.info(&format!("[supi] Press Ctrl+C to exit, press hotkey '{}' to restart, or send signal({}) to restart", key, self.restart_signal));
+        } else {
+            self.output.info(&format!(
+                "[supi] Press Ctrl+C to exit, or send signal({}) to restart",
+                self.restart_signal
+            ));
+        }
+    }
+
+    /// Tell the user how to start a child that wasn't started automatically
+    fn print_start_hint(&self) {
+        let restart_key = self
+            .hotkey_listener
+            .as_ref()
+            .and(self.keymap.key_for(Action::Restart));
+        match restart_key {
+            Some(key) => self.output.info(&format!(
+                "[supi] Child process not started (--no-autostart), press hotkey '{}' or send signal({}) to start",
+                key, self.restart_signal
+            )),
+            None => self.output.info(&format!(
+                "[supi] Child process not started (--no-autostart), send signal({}) to start",
+                self.restart_signal
+            )),
+        }
+    }
+
+    /// Print the last stderr lines of the failed run in a summary block
+    fn print_crash_summary(&self) {
+        if self.crash_summary_lines == 0 {
+            return;
+        }
+
+        let run = self.process_manager.run();
+        let lines = self
+            .buffer
+            .last_lines(run, Stream::Stderr, self.crash_summary_lines);
+        if lines.is_empty() {
+            return;
+        }
+
+        self.output.elog(&format!(
+            "[supi] ---- last {} stderr lines of run #{} ----",
+            lines.len(),
+            run
+        ));
+        for line in &lines {
+            self.output.einfo(line);
+        }
+        self.output
+            .elog("[supi] ---------------------------------------");
+    }
+
+    /// Tell subscribers that a restart was asked for, before the debounce check
+    fn request_restart(&self, source: RestartSource) {
+        self.process_manager
+            .events()
+            .emit(SupervisorEvent::RestartRequested { source });
+    }
+
     /// Check if restart should be allowed based on debounce settings.
     /// Returns true if restart is allowed, false if debounced.
     fn should_allow_restart(&mut self) -> bool {
@@ -59,6 +782,11 @@ impl Supervisor {
                     "[supi] Restart request ignored (debounce active, {}ms remaining)",
                     remaining
                 ));
+                self.process_manager
+                    .events()
+                    .emit(SupervisorEvent::Debounced {
+                        remaining: Duration::from_millis(remaining),
+                    });
                 return false;
             }
         }
@@ -68,66 +796,253 @@ impl Supervisor {
         true
     }
 
-    pub async fn run(&mut self) -> Result<()> {
-        // Spawn initial process
-        self.process_manager.spawn().await?;
+    /// Restart the child when the watchdog finds it over a resource limit.
+    /// Not debounced, the watchdog only fires once per sample interval.
+    async fn check_resources(&mut self) -> Result<()> {
+        let (Some(watchdog), Some(pid)) = (&mut self.watchdog, self.process_manager.pid()) else {
+            return Ok(());
+        };
+        if let Some(reason) = watchdog.check(pid) {
+            self.output.elog(&format!(
+                "[supi] Watchdog: {}, restarting run #{}...",
+                reason,
+                self.process_manager.run()
+            ));
+            self.report_hidden_lines_for_run();
+            self.process_manager
+                .restart_for(StopReason::Watchdog)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Restart the child for --restart-every / --restart-cron, through the same
+    /// debounce and graceful stop as any other restart, and schedule the next one
+    async fn handle_scheduled_restart(&mut self) -> Result<()> {
+        let Some(schedule) = &self.schedule else {
+            return Ok(());
+        };
+        let description = schedule.describe();
+
+        if self.process_manager.is_running() {
+            self.output
+                .log(&format!("[supi] Scheduled restart ({})", description));
+            self.request_restart(RestartSource::Schedule);
+            if self.should_allow_restart() {
+                self.restart_or_start().await?;
+            }
+        } else {
+            self.output.log(&format!(
+                "[supi] Scheduled restart ({}) skipped, child process not running",
+                description
+            ));
+        }
+
+        if let Some(schedule) = &mut self.schedule {
+            schedule.advance()?;
+            self.output.log(&format!(
+                "[supi] Next scheduled restart at {}",
+                schedule.describe_next()
+            ));
+        }
+        Ok(())
+    }
+
+    /// When the running child reaches --max-runtime. A runtime too long to be a
+    /// point in time never ends the run.
+    fn runtime_deadline(&self) -> Option<Instant> {
+        let remaining = self
+            .max_runtime?
+            .saturating_sub(self.process_manager.uptime()?);
+        Instant::now().checked_add(remaining)
+    }
+
+    /// Gracefully stop a child that reached --max-runtime, then restart it, or exit
+    /// with the timeout code when --stop-on-child-exit is set.
+    /// Returns `Some(exit_code)` when the supervisor should exit.
+    async fn handle_max_runtime(&mut self) -> Result<Option<i32>> {
+        self.output.elog(&format!(
+            "[supi] Run #{} reached the maximum runtime of {}, stopping...",
+            self.process_manager.run(),
+            format_duration(self.max_runtime.unwrap_or_default())
+        ));
+        self.report_hidden_lines_for_run();
+
+        if self.stop_on_child_exit {
+            self.process_manager.stop(StopReason::MaxRuntime).await?;
+            self.output.log(&format!(
+                "[supi] Exiting with code {} (--max-runtime exceeded, --stop-on-child-exit is set)",
+                TIMEOUT_EXIT_CODE
+            ));
+            return Ok(Some(TIMEOUT_EXIT_CODE));
+        }
+
+        self.process_manager
+            .restart_for(StopReason::MaxRuntime)
+            .await?;
+        Ok(None)
+    }
+
+    /// Run the supervisor event loop. Returns the exit code for supi.
+    pub async fn run(&mut self) -> Result<i32> {
+        let result = self.supervise().await;
+        // Handles waiting for an answer, or asking later, get `Error::Stopped`
+        self.requests.close();
+        while self.requests.try_recv().is_ok() {}
+        result
+    }
+
+    async fn supervise(&mut self) -> Result<i32> {
+        if self.init_mode {
+            self.reaper = Some(
+                Reaper::new(self.output.clone(), self.own_children.clone())
+                    .map_err(|e| Error::setup("the reaper", e))?,
+            );
+        }
+        // Raw mode is enabled in the supervisor after command validation
+        self.start_hotkey_listener();
+
+        // Spawn the initial process. A deferred start can't validate the command by
+        // spawning it, so it is checked up front instead.
+        let mut start_at = None;
+        if !self.autostart {
+            self.process_manager.check_command()?;
+        } else if let Some(delay) = self.start_delay {
+            self.process_manager.check_command()?;
+            self.output.log(&format!(
+                "[supi] Starting child process in {}",
+                format_duration(delay)
+            ));
+            start_at = Some(Instant::now().checked_add(delay).ok_or_else(|| {
+                Error::invalid_option("start_delay", format_duration(delay), "too large")
+            })?);
+        } else {
+            self.process_manager.spawn().await?;
+        }
 
         // Only enable raw mode after successfully spawning the process
         // This prevents raw mode from being activated when the command doesn't exist
         if let Some(ref mut listener) = self.hotkey_listener {
-            listener.enable_raw_mode()?;
+            listener
+                .enable_raw_mode()
+                .map_err(|e| Error::setup("the terminal", e))?;
             self.output.info(&format!(
-                "[supi] Hotkey listener active: press '{}' to restart",
-                self.restart_hotkey
+                "[supi] Hotkey listener active: {}",
+                self.keymap.describe()
             ));
         }
 
+        if !self.autostart {
+            self.print_start_hint();
+        }
+
+        let mut exit_code = None;
+        let mut filter_report = interval(FILTER_REPORT_INTERVAL);
+        filter_report.reset();
+        self.process_manager.events().emit(SupervisorEvent::Ready);
+
         loop {
+            self.update_status_line();
+            let runtime_deadline = self.runtime_deadline();
+            // A hotkey or signal may have started the child before the delay ran out
+            if self.process_manager.run() > 0 {
+                start_at = None;
+            }
+
             tokio::select! {
 
                 // Handle signals
                 Some(signal_event) = self.signal_handler.next() => {
-                    match signal_event {
-                        SignalEvent::Terminate(signal_name) => {
-                            self.output.log(&format!("[supi] Received {} signal, shutting down...", signal_name));
-                            self.process_manager.shutdown().await?;
-                            break;
-                        }
-                        SignalEvent::Restart(signal_name) => {
-                            self.output.log(&format!("[supi] Received {} signal", signal_name));
-
-                            if !self.should_allow_restart() {
-                                continue; // Skip restart due to debounce
-                            }
-
-                            if self.process_manager.is_running() {
-                                self.process_manager.restart().await?;
-                            } else {
-                                self.output.log("[supi] Child process not running, starting...");
-                                self.process_manager.spawn().await?;
-                            }
-                        }
+                    if self.handle_signal(signal_event).await? {
+                        break;
                     }
                 }
 
                 // Handle hotkey press
-                Some(_) = async {
+                Some(command) = async {
                     match &mut self.hotkey_listener {
                         Some(listener) => listener.next().await,
                         None => std::future::pending().await,
                     }
                 } => {
-                    self.output.log("[supi] Hotkey pressed, restarting...");
+                    if self.handle_command(command).await? {
+                        break;
+                    }
+                }
 
-                    if !self.should_allow_restart() {
-                        continue; // Skip restart due to debounce
+                // Handle requests from supervisor handles
+                Some(request) = self.requests.recv() => {
+                    if self.handle_request(request).await? {
+                        break;
                     }
+                }
 
-                    if self.process_manager.is_running() {
-                        self.process_manager.restart().await?;
-                    } else {
-                        self.output.log("[supi] Child process not running, starting...");
-                        self.process_manager.spawn().await?;
+                // Handle output trigger matches
+                Some(trigger) = self.trigger_matches.recv() => {
+                    if let Some(code) = self.handle_trigger(trigger).await? {
+                        exit_code = Some(code);
+                        break;
+                    }
+                }
+
+                // Periodically report lines hidden by output filters
+                _ = filter_report.tick(), if self.filter.has_rules() => {
+                    self.report_hidden_lines();
+                }
+
+                // Reap orphaned descendants in init mode
+                _ = async {
+                    match &mut self.reaper {
+                        Some(reaper) => reaper.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    if let Some(reaper) = &self.reaper {
+                        reaper.reap_orphans(self.process_manager.pid());
+                    }
+                }
+
+                // Sample the child's resource usage and restart it when over a limit
+                _ = async {
+                    match &mut self.watchdog {
+                        Some(watchdog) => watchdog.tick().await,
+                        None => std::future::pending().await,
+                    }
+                }, if self.process_manager.is_running() => {
+                    self.check_resources().await?;
+                }
+
+                // Start the child once --start-delay has passed
+                _ = async {
+                    match start_at {
+                        Some(start_at) => tokio::time::sleep_until(start_at).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    start_at = None;
+                    self.process_manager.spawn().await?;
+                }
+
+                // Restart on --restart-every / --restart-cron
+                _ = async {
+                    match &self.schedule {
+                        Some(schedule) => schedule.wait().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    self.handle_scheduled_restart().await?;
+                }
+
+                // Stop runs that exceed --max-runtime
+                _ = async {
+                    match runtime_deadline {
+                        Some(deadline) => tokio::time::sleep_until(deadline).await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    if let Some(code) = self.handle_max_runtime().await? {
+                        exit_code = Some(code);
+                        break;
                     }
                 }
 
@@ -137,17 +1052,17 @@ impl Supervisor {
                         Ok(exit_status) => {
                             self.output.log(&format!("[supi] Child process exited with status: {}", exit_status));
 
+                            if !exit_status.success() {
+                                self.print_crash_summary();
+                            }
+                            self.report_hidden_lines_for_run();
+
                             if self.stop_on_child_exit {
                                 self.output.log("[supi] Exiting (--stop-on-child-exit is set)");
                                 break;
                             } else {
                                 self.output.log("[supi] Child process exited, but supervisor continues running");
-                                if self.hotkey_listener.is_some() {
-                                    self.output.info(&format!("[supi] Press Ctrl+C to exit, press hotkey '{}' to restart, or send signal({}) to restart", self.restart_hotkey, self.restart_signal));
-                                } else {
-                                    self.output.info(&format!("[supi] Press Ctrl+C to exit, or send signal({}) to restart",
-                                    self.restart_signal));
-                                }
+                                self.print_restart_hint();
                                 // Continue loop, waiting for signals
                             }
                         }
@@ -160,6 +1075,26 @@ impl Supervisor {
             }
         }
 
-        Ok(())
+        if self.filter.hidden_total() > 0 {
+            self.output.log(&format!(
+                "[supi] {} lines hidden by output filters in total",
+                self.filter.hidden_total()
+            ));
+        }
+
+        // In init mode supi exits with the child's status, like tini and dumb-init
+        let exit_code = match (exit_code, &self.reaper) {
+            (Some(code), _) => code,
+            (None, Some(reaper)) => {
+                reaper.reap_orphans(self.process_manager.pid());
+                let code = self.process_manager.last_exit_code().unwrap_or(0);
+                self.output
+                    .log(&format!("[supi] Exiting with child exit code {}", code));
+                code
+            }
+            (None, None) => 0,
+        };
+
+        Ok(exit_code)
     }
 }
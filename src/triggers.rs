@@ -0,0 +1,218 @@
+use crate::buffer::Stream;
+use crate::units::parse_duration;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// What to do when a trigger rule matches a line of child output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerAction {
+    Restart,
+    Stop,
+    Exit(i32),
+    Run(String),
+    Notify,
+}
+
+impl fmt::Display for TriggerAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerAction::Restart => write!(f, "restart"),
+            TriggerAction::Stop => write!(f, "stop"),
+            TriggerAction::Exit(code) => write!(f, "exit({})", code),
+            TriggerAction::Run(command) => write!(f, "run({})", command),
+            TriggerAction::Notify => write!(f, "notify"),
+        }
+    }
+}
+
+/// A single "when output matches X, do Y" rule
+#[derive(Debug, Clone)]
+pub struct TriggerRule {
+    pub pattern: Regex,
+    pub action: TriggerAction,
+    pub stream: Option<Stream>,
+    pub cooldown: Duration,
+    pub max_matches: Option<u32>,
+}
+
+impl TriggerRule {
+    /// Parse a rule spec of the form `<action>[,<option>...]:<regex>`.
+    ///
+    /// Actions: `restart`, `stop`, `exit(N)`, `run(COMMAND)`, `notify`
+    /// Options: `stdout`, `stderr`, `cooldown=<duration>`, `max=<count>`
+    ///
+    /// Examples: `restart:EADDRINUSE`, `exit(0),stdout,max=1:All tests passed`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (head, pattern) = split_spec(spec).context("expected <action>:<regex>")?;
+
+        let (action, options) =
+            parse_action(head).with_context(|| format!("Invalid trigger action '{}'", head))?;
+
+        let mut rule = TriggerRule {
+            pattern: Regex::new(pattern).with_context(|| format!("Invalid regex '{}'", pattern))?,
+            action,
+            stream: None,
+            cooldown: Duration::ZERO,
+            max_matches: None,
+        };
+
+        for option in options.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            match option.split_once('=') {
+                None if option == "stdout" => rule.stream = Some(Stream::Stdout),
+                None if option == "stderr" => rule.stream = Some(Stream::Stderr),
+                Some(("cooldown", value)) => rule.cooldown = parse_duration(value)?,
+                Some(("max", value)) => {
+                    rule.max_matches = Some(
+                        value
+                            .parse()
+                            .with_context(|| format!("Invalid max match count '{}'", value))?,
+                    )
+                }
+                _ => anyhow::bail!(
+                    "Unknown trigger option '{}'. Supported: stdout, stderr, cooldown=<duration>, max=<count>",
+                    option
+                ),
+            }
+        }
+
+        Ok(rule)
+    }
+}
+
+/// Split `<head>:<regex>` at the first colon outside of parentheses
+fn split_spec(spec: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ':' if depth == 0 => return Some((&spec[..i], &spec[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse the action at the start of a rule head, returning it and the remaining options
+fn parse_action(head: &str) -> Result<(TriggerAction, &str)> {
+    let head = head.trim();
+    let name_end = head.find(['(', ',']).unwrap_or(head.len());
+    let name = &head[..name_end];
+
+    let (argument, rest) = if head[name_end..].starts_with('(') {
+        let close = head
+            .rfind(')')
+            .context("Missing closing parenthesis in trigger action")?;
+        (Some(&head[name_end + 1..close]), &head[close + 1..])
+    } else {
+        (None, &head[name_end..])
+    };
+
+    let action = match (name, argument) {
+        ("restart", None) => TriggerAction::Restart,
+        ("stop", None) => TriggerAction::Stop,
+        ("exit", None) => TriggerAction::Exit(0),
+        ("exit", Some(code)) => TriggerAction::Exit(
+            code.trim()
+                .parse()
+                .with_context(|| format!("Invalid exit code '{}'", code))?,
+        ),
+        ("run", Some(command)) if !command.trim().is_empty() => {
+            TriggerAction::Run(command.to_string())
+        }
+        ("notify", None) => TriggerAction::Notify,
+        _ => anyhow::bail!(
+            "Unsupported action '{}'. Supported: restart, stop, exit(N), run(COMMAND), notify",
+            head
+        ),
+    };
+
+    Ok((action, rest.trim_start_matches(',')))
+}
+
+/// A trigger rule that matched a line of child output
+#[derive(Debug, Clone)]
+pub struct TriggerMatch {
+    pub action: TriggerAction,
+    pub pattern: String,
+    pub stream: Stream,
+    pub line: String,
+}
+
+#[derive(Default)]
+struct RuleState {
+    last_fired: Option<Instant>,
+    matches: u32,
+}
+
+struct TriggerSetInner {
+    rules: Vec<TriggerRule>,
+    state: Mutex<Vec<RuleState>>,
+    sender: mpsc::UnboundedSender<TriggerMatch>,
+}
+
+/// Set of trigger rules checked against every line of child output.
+/// Matches are sent to the receiver returned by `TriggerSet::new`.
+/// Cheap to clone (shared state).
+#[derive(Clone)]
+pub struct TriggerSet {
+    inner: Arc<TriggerSetInner>,
+}
+
+impl TriggerSet {
+    pub fn new(rules: Vec<TriggerRule>) -> (Self, mpsc::UnboundedReceiver<TriggerMatch>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let state = rules.iter().map(|_| RuleState::default()).collect();
+        let set = Self {
+            inner: Arc::new(TriggerSetInner {
+                rules,
+                state: Mutex::new(state),
+                sender,
+            }),
+        };
+        (set, receiver)
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.inner.rules.len()
+    }
+
+    /// Check a line against all rules, honoring stream filters, cooldowns and max matches
+    pub fn check(&self, stream: Stream, line: &str) {
+        if self.inner.rules.is_empty() {
+            return;
+        }
+
+        let mut state = self.inner.state.lock().unwrap();
+        for (rule, rule_state) in self.inner.rules.iter().zip(state.iter_mut()) {
+            if rule.stream.is_some_and(|s| s != stream) || !rule.pattern.is_match(line) {
+                continue;
+            }
+            if rule
+                .max_matches
+                .is_some_and(|max| rule_state.matches >= max)
+            {
+                continue;
+            }
+            if let Some(last) = rule_state.last_fired {
+                if last.elapsed() < rule.cooldown {
+                    continue;
+                }
+            }
+
+            rule_state.last_fired = Some(Instant::now());
+            rule_state.matches += 1;
+
+            let _ = self.inner.sender.send(TriggerMatch {
+                action: rule.action.clone(),
+                pattern: rule.pattern.to_string(),
+                stream,
+                line: line.to_string(),
+            });
+        }
+    }
+}
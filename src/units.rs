@@ -0,0 +1,100 @@
+use anyhow::Result;
+use std::time::Duration;
+
+/// Parse a human-friendly duration like `500ms`, `5s`, `10m`, `2h` or `1d`.
+/// A plain number is interpreted as seconds.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let s = input.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration '{}'", input))?;
+
+    let seconds = match unit.trim().to_lowercase().as_str() {
+        "ms" => value / 1000.0,
+        "" | "s" | "sec" => value,
+        "m" | "min" => value * 60.0,
+        "h" => value * 60.0 * 60.0,
+        "d" => value * 60.0 * 60.0 * 24.0,
+        _ => anyhow::bail!(
+            "Invalid duration '{}'. Supported units: ms, s, m, h, d",
+            input
+        ),
+    };
+
+    Duration::try_from_secs_f64(seconds)
+        .map_err(|_| anyhow::anyhow!("Invalid duration '{}': too large", input))
+}
+
+/// Format a duration for humans, e.g. `350ms`, `42s`, `5m 3s` or `2h 10m`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs == 0 {
+        return format!("{}ms", duration.as_millis());
+    }
+
+    let (days, hours, minutes, seconds) = (
+        secs / 86400,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+    );
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// Parse a byte size like `4096`, `512K`, `1.5G` or `2GiB` (binary units)
+pub fn parse_size(input: &str) -> Result<u64> {
+    let s = input.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let value: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size '{}'", input))?;
+
+    let multiplier: u64 = match unit.trim().to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        "T" | "TB" | "TIB" => 1 << 40,
+        _ => anyhow::bail!("Invalid size '{}'. Supported units: K, M, G, T", input),
+    };
+
+    Ok((value * multiplier as f64) as u64)
+}
+
+/// Format a byte size for humans in binary units, e.g. `512B`, `12.5K`, `1.5G`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["K", "M", "G", "T"];
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let formatted = format!("{:.1}", value);
+    format!(
+        "{}{}",
+        formatted.strip_suffix(".0").unwrap_or(&formatted),
+        UNITS[unit]
+    )
+}
@@ -0,0 +1,277 @@
+use crate::error::Error;
+use crate::units::{format_duration, format_size, parse_duration, parse_size};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use tokio::time::{interval, Duration, Instant, Interval, MissedTickBehavior};
+
+/// Resource usage of the child's process tree at one point in time
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    memory: u64,
+    cpu_ticks: u64,
+}
+
+/// Usage tracked for the current run; reset whenever the child PID changes
+#[derive(Debug, Default)]
+struct RunUsage {
+    pid: u32,
+    last: Option<Sample>,
+    cpu: Option<f64>,
+    peak_memory: u64,
+    peak_cpu: f64,
+    cpu_over_since: Option<Instant>,
+}
+
+/// Samples the child's process tree from /proc and reports when it uses too much
+/// memory, or too much CPU for too long
+pub struct Watchdog {
+    max_memory: Option<u64>,
+    max_cpu: Option<f64>,
+    max_cpu_for: Duration,
+    sample_every: Duration,
+    ticker: Interval,
+    usage: RunUsage,
+    page_size: u64,
+    ticks_per_second: u64,
+}
+
+impl Watchdog {
+    /// Parse `--max-memory`, `--max-cpu`, `--max-cpu-for` and `--sample-interval`
+    pub fn new(
+        max_memory: Option<&str>,
+        max_cpu: Option<&str>,
+        max_cpu_for: &str,
+        sample_interval: &str,
+    ) -> crate::Result<Self> {
+        let max_memory = max_memory
+            .map(|value| {
+                parse_size(value).map_err(|e| Error::invalid_option("max_memory", value, e))
+            })
+            .transpose()?;
+        let max_cpu = max_cpu
+            .map(|value| {
+                parse_percent(value).map_err(|e| Error::invalid_option("max_cpu", value, e))
+            })
+            .transpose()?;
+        let max_cpu_for = parse_duration(max_cpu_for)
+            .map_err(|e| Error::invalid_option("max_cpu_for", max_cpu_for, e))?;
+        let sample_every = parse_duration(sample_interval)
+            .ok()
+            .filter(|every| !every.is_zero())
+            .context("expected a duration like 2s")
+            .and_then(|every| {
+                // The ticker schedules each sample as a point in time
+                Instant::now()
+                    .checked_add(every)
+                    .map(|_| every)
+                    .context("too large")
+            })
+            .map_err(|e| Error::invalid_option("sample_interval", sample_interval, e))?;
+
+        if !std::path::Path::new("/proc/self/stat").exists() {
+            return Err(Error::setup(
+                "the watchdog",
+                "it needs /proc, which this system doesn't have",
+            ));
+        }
+
+        let mut ticker = interval(sample_every);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // SAFETY: sysconf only reads system configuration
+        let (page_size, ticks_per_second) = unsafe {
+            (
+                nix::libc::sysconf(nix::libc::_SC_PAGESIZE),
+                nix::libc::sysconf(nix::libc::_SC_CLK_TCK),
+            )
+        };
+
+        Ok(Self {
+            max_memory,
+            max_cpu,
+            max_cpu_for,
+            sample_every,
+            ticker,
+            usage: RunUsage::default(),
+            page_size: page_size.max(1) as u64,
+            ticks_per_second: ticks_per_second.max(1) as u64,
+        })
+    }
+
+    /// Summary for the startup log, e.g.
+    /// `restart when memory > 1.5G or CPU > 95% for 30s, sampled every 2s`
+    pub fn describe(&self) -> String {
+        let mut limits = Vec::new();
+        if let Some(memory) = self.max_memory {
+            limits.push(format!("memory > {}", format_size(memory)));
+        }
+        if let Some(cpu) = self.max_cpu {
+            limits.push(format!(
+                "CPU > {}% for {}",
+                cpu,
+                format_duration(self.max_cpu_for)
+            ));
+        }
+        format!(
+            "restart when {}, sampled every {}",
+            limits.join(" or "),
+            format_duration(self.sample_every)
+        )
+    }
+
+    /// Wait until the next sample is due
+    pub async fn tick(&mut self) {
+        self.ticker.tick().await;
+    }
+
+    /// Sample the process tree rooted at `pid`. Returns why the child should be
+    /// restarted when a limit was exceeded.
+    pub fn check(&mut self, pid: u32) -> Option<String> {
+        if self.usage.pid != pid {
+            self.usage = RunUsage {
+                pid,
+                ..RunUsage::default()
+            };
+        }
+        let sample = self.sample(pid)?;
+
+        let usage = &mut self.usage;
+        usage.peak_memory = usage.peak_memory.max(sample.memory);
+        let previous = usage.last.replace(sample);
+
+        // CPU is measured between two samples, so the first one has none
+        if let Some(previous) = previous {
+            let elapsed = sample.at.duration_since(previous.at).as_secs_f64();
+            if elapsed > 0.0 {
+                let seconds = sample.cpu_ticks.saturating_sub(previous.cpu_ticks) as f64
+                    / self.ticks_per_second as f64;
+                let cpu = seconds / elapsed * 100.0;
+                usage.cpu = Some(cpu);
+                usage.peak_cpu = usage.peak_cpu.max(cpu);
+
+                match self.max_cpu {
+                    Some(max_cpu) if cpu > max_cpu => {
+                        let since = *usage.cpu_over_since.get_or_insert(previous.at);
+                        let over_for = sample.at.duration_since(since);
+                        if over_for >= self.max_cpu_for {
+                            return Some(format!(
+                                "CPU above {}% for {} (now {:.0}%, peak {:.0}%)",
+                                max_cpu,
+                                format_duration(over_for),
+                                cpu,
+                                usage.peak_cpu
+                            ));
+                        }
+                    }
+                    _ => usage.cpu_over_since = None,
+                }
+            }
+        }
+
+        match self.max_memory {
+            Some(max_memory) if sample.memory > max_memory => Some(format!(
+                "memory limit {} exceeded (now {}, peak {})",
+                format_size(max_memory),
+                format_size(sample.memory),
+                format_size(usage.peak_memory)
+            )),
+            _ => None,
+        }
+    }
+
+    /// Current and peak usage of the running child, e.g.
+    /// `412M memory (peak 1.2G), 3% CPU (peak 97%)`
+    pub fn usage(&self, pid: u32) -> Option<String> {
+        let usage = &self.usage;
+        let last = usage.last.filter(|_| usage.pid == pid)?;
+        let mut text = format!(
+            "{} memory (peak {})",
+            format_size(last.memory),
+            format_size(usage.peak_memory)
+        );
+        if let Some(cpu) = usage.cpu {
+            text.push_str(&format!(", {:.0}% CPU (peak {:.0}%)", cpu, usage.peak_cpu));
+        }
+        Some(text)
+    }
+
+    /// Sum RSS and CPU time over `pid` and all its descendants
+    fn sample(&self, pid: u32) -> Option<Sample> {
+        let processes = read_processes();
+        processes.get(&pid)?;
+
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (child, stat) in &processes {
+            children.entry(stat.ppid).or_default().push(*child);
+        }
+
+        let (mut memory, mut cpu_ticks) = (0, 0);
+        let mut pending = vec![pid];
+        while let Some(pid) = pending.pop() {
+            if let Some(stat) = processes.get(&pid) {
+                memory += stat.rss_pages * self.page_size;
+                cpu_ticks += stat.cpu_ticks;
+            }
+            if let Some(children) = children.get(&pid) {
+                pending.extend(children);
+            }
+        }
+
+        Some(Sample {
+            at: Instant::now(),
+            memory,
+            cpu_ticks,
+        })
+    }
+}
+
+/// The fields of /proc/<pid>/stat the watchdog needs
+struct ProcStat {
+    ppid: u32,
+    cpu_ticks: u64,
+    rss_pages: u64,
+}
+
+/// Read every process from /proc. Processes that exit while scanning are skipped.
+fn read_processes() -> HashMap<u32, ProcStat> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return HashMap::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            Some((pid, parse_stat(&stat)?))
+        })
+        .collect()
+}
+
+/// Parse /proc/<pid>/stat. The command name may contain spaces and parentheses,
+/// so the fields are counted from the last ')'.
+fn parse_stat(stat: &str) -> Option<ProcStat> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let field = |n: usize| fields.get(n - 3)?.parse::<u64>().ok();
+
+    Some(ProcStat {
+        ppid: field(4)? as u32,
+        // utime + stime, plus the time of children that were already waited for,
+        // so short-lived subprocesses still count
+        cpu_ticks: field(14)? + field(15)? + field(16)? + field(17)?,
+        rss_pages: field(24)?,
+    })
+}
+
+/// Parse a CPU percentage like `95%` or `250` (100% is one core)
+fn parse_percent(value: &str) -> Result<f64> {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .parse::<f64>()
+        .ok()
+        .filter(|percent| *percent > 0.0)
+        .context("expected a percentage like 95% (100% is one core)")
+}
@@ -49,8 +49,11 @@ echo "All children completed"
         std::fs::set_permissions(temp_file.path(), perms).unwrap();
     }
 
+    // Close our write handle so exec doesn't fail with ETXTBSY on Linux
+    let temp_path = temp_file.into_temp_path();
+
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.arg(temp_file.path().to_str().unwrap());
+    cmd.arg(temp_path.to_str().unwrap());
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     let supi_pid = child.process_id().unwrap();
@@ -64,7 +67,7 @@ echo "All children completed"
     let bash_pid = {
         use std::process::Command;
         let output = Command::new("pgrep")
-            .args(&["-P", &supi_pid.to_string()])
+            .args(["-P", &supi_pid.to_string()])
             .output()
             .expect("Failed to run pgrep");
 
@@ -81,7 +84,7 @@ echo "All children completed"
         if let Some(bash_pid) = bash_pid {
             use std::process::Command;
             let output = Command::new("pgrep")
-                .args(&["-P", &bash_pid.to_string()])
+                .args(["-P", &bash_pid.to_string()])
                 .output()
                 .expect("Failed to run pgrep");
 
@@ -122,7 +125,7 @@ echo "All children completed"
         if let Some(bash_pid) = bash_pid {
             use std::process::Command;
             let output = Command::new("ps")
-                .args(&["-p", &bash_pid.to_string()])
+                .args(["-p", &bash_pid.to_string()])
                 .output()
                 .expect("Failed to run ps");
 
@@ -138,7 +141,7 @@ echo "All children completed"
         for sleep_pid in sleep_pids {
             use std::process::Command;
             let output = Command::new("ps")
-                .args(&["-p", &sleep_pid.to_string()])
+                .args(["-p", &sleep_pid.to_string()])
                 .output()
                 .expect("Failed to run ps");
 
@@ -201,8 +204,11 @@ echo "All children completed"
         std::fs::set_permissions(temp_file.path(), perms).unwrap();
     }
 
+    // Close our write handle so exec doesn't fail with ETXTBSY on Linux
+    let temp_path = temp_file.into_temp_path();
+
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.arg(temp_file.path().to_str().unwrap());
+    cmd.arg(temp_path.to_str().unwrap());
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     let supi_pid = child.process_id().unwrap();
@@ -216,7 +222,7 @@ echo "All children completed"
     let first_bash_pid = {
         use std::process::Command;
         let output = Command::new("pgrep")
-            .args(&["-P", &supi_pid.to_string()])
+            .args(["-P", &supi_pid.to_string()])
             .output()
             .expect("Failed to run pgrep");
 
@@ -232,7 +238,7 @@ echo "All children completed"
         if let Some(bash_pid) = first_bash_pid {
             use std::process::Command;
             let output = Command::new("pgrep")
-                .args(&["-P", &bash_pid.to_string()])
+                .args(["-P", &bash_pid.to_string()])
                 .output()
                 .expect("Failed to run pgrep");
 
@@ -271,7 +277,7 @@ echo "All children completed"
         if let Some(bash_pid) = first_bash_pid {
             use std::process::Command;
             let output = Command::new("ps")
-                .args(&["-p", &bash_pid.to_string()])
+                .args(["-p", &bash_pid.to_string()])
                 .output()
                 .expect("Failed to run ps");
 
@@ -286,7 +292,7 @@ echo "All children completed"
         for sleep_pid in first_sleep_pids {
             use std::process::Command;
             let output = Command::new("ps")
-                .args(&["-p", &sleep_pid.to_string()])
+                .args(["-p", &sleep_pid.to_string()])
                 .output()
                 .expect("Failed to run ps");
 
@@ -0,0 +1,94 @@
+// Feature: Command palette for typed supervisor commands
+// Tests for ':' commands, tab completion and history
+
+mod cli_test_utils;
+
+use cli_test_utils::output_after_typing;
+
+// Manual test: cargo run -- bash -- -c "echo started; sleep 30"
+//              (type ':restart' and press Enter)
+#[test]
+fn test_palette_restart_command() {
+    let output = output_after_typing(&[], "echo 'child started'; sleep 30", &[b":restart\r"]);
+
+    assert!(
+        output.contains("Command: restart"),
+        "Expected the submitted command to be logged. Output:\n{}",
+        output
+    );
+    assert_eq!(
+        output.matches("child started\r").count(),
+        2,
+        "Expected ':restart' to restart the child. Output:\n{}",
+        output
+    );
+}
+
+// Test that ':env set' changes the environment of the next run
+#[test]
+fn test_palette_env_set_applies_on_restart() {
+    let output = output_after_typing(
+        &[],
+        "echo \"greeting=${GREETING:-none}\"; sleep 30",
+        &[b":env set GREETING=hello\r", b":restart\r"],
+    );
+
+    assert!(
+        output.contains("greeting=none\r"),
+        "Expected the first run without the variable. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("greeting=hello\r"),
+        "Expected the restarted run to see the variable. Output:\n{}",
+        output
+    );
+}
+
+// Test that ':signal' sends a signal to the child process group
+#[test]
+fn test_palette_signal_command() {
+    let output = output_after_typing(
+        &[],
+        "trap 'echo got hup' HUP; while true; do sleep 0.1; done",
+        &[b":signal HUP\r", b":signal BOGUS\r"],
+    );
+
+    assert!(
+        output.contains("got hup\r"),
+        "Expected the child to receive SIGHUP. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Unknown signal: BOGUS"),
+        "Expected an error for an unknown signal. Output:\n{}",
+        output
+    );
+}
+
+// Manual test: type ':deb<Tab>0', Enter, ':rest<Tab>', Enter, then ':' and Up, Enter
+#[test]
+fn test_palette_tab_completion_and_history() {
+    let output = output_after_typing(
+        &[],
+        "echo 'child started'; sleep 30",
+        &[b":deb\t0\r", b":rest\t\r", b":\x1b[A\r", b":frobnicate\r"],
+    );
+
+    assert!(
+        output.contains("Restart debounce disabled"),
+        "Expected completed ':debounce 0' to run. Output:\n{}",
+        output
+    );
+    assert_eq!(
+        output.matches("child started\r").count(),
+        3,
+        "Expected a completed and a recalled ':restart'. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Unknown command 'frobnicate'"),
+        "Expected an error for an unknown command. Output:\n{}",
+        output
+    );
+}
@@ -0,0 +1,141 @@
+// Feature: Shell completions and man page
+// Tests for `supi completions <shell>` and `supi man`
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+/// Ask supi for completion candidates the way the bash script does, for the last word of `line`
+fn complete(line: &[&str], envs: &[(&str, &std::path::Path)]) -> Vec<String> {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.env("SUPI_COMPLETE", "bash")
+        .env("_CLAP_COMPLETE_INDEX", (line.len() - 1).to_string())
+        .env("_CLAP_COMPLETE_COMP_TYPE", "9")
+        .env("_CLAP_COMPLETE_SPACE", "true")
+        .env("_CLAP_IFS", "\n")
+        .arg("--")
+        .args(line);
+    for (key, value) in envs {
+        cmd.env(key, value);
+    }
+    let output = cmd.assert().success().get_output().stdout.clone();
+    String::from_utf8_lossy(&output)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+// Manual test: source <(cargo run -q -- completions bash), then type: supi --restart-signal SIG<TAB>
+#[test]
+fn test_completions_print_scripts() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["completions", "bash"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("SUPI_COMPLETE=\"bash\""))
+        .stdout(predicate::str::contains("-F _clap_complete_supi supi"));
+
+    for shell in ["zsh", "fish", "elvish"] {
+        Command::cargo_bin("supi")
+            .unwrap()
+            .args(["completions", shell])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("SUPI_COMPLETE"));
+    }
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["completions", "tcsh"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'tcsh'"));
+}
+
+// Manual test: cargo run -q -- man | man -l -
+#[test]
+fn test_man_page_renders_options_and_subcommands() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .arg("man")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".TH supi 1"))
+        .stdout(predicate::str::contains(r"\-\-restart\-signal"))
+        .stdout(predicate::str::contains(r"\-\-stop\-on\-child\-exit"))
+        .stdout(predicate::str::contains("completions"));
+}
+
+// Test that option values complete from signal, color and action names
+#[test]
+fn test_option_values_complete() {
+    let signals = complete(&["supi", "--restart-signal", "SIGUS"], &[]);
+    assert_eq!(signals, ["SIGUSR1", "SIGUSR2"], "Signal names");
+
+    let forwarded = complete(&["supi", "--forward-signal", "SIGHUP,SIGUS"], &[]);
+    assert!(
+        forwarded.contains(&"SIGHUP,SIGUSR2".to_string()),
+        "Expected comma-separated signals to complete: {:?}",
+        forwarded
+    );
+
+    let colors = complete(&["supi", "--log-color", "g"], &[]);
+    assert_eq!(colors, ["green"], "Color names");
+
+    let mappings = complete(&["supi", "--on-signal", "SIGHUP=re"], &[]);
+    assert_eq!(mappings, ["SIGHUP=restart"], "Signal actions");
+
+    let mappings = complete(&["supi", "--on-signal", "SIGHUP=p"], &[]);
+    assert_eq!(
+        mappings,
+        ["SIGHUP=pause"],
+        "Signal actions without the palette"
+    );
+
+    let subcommands = complete(&["supi", "comp"], &[]);
+    assert_eq!(subcommands, ["completions"], "Subcommands");
+}
+
+// Test that `supi attach` completes the names of running sessions
+#[test]
+fn test_session_names_complete() {
+    let dir = tempfile::tempdir().unwrap();
+    // A live PID and a socket path are all the registry checks for
+    for name in ["api", "worker"] {
+        std::fs::write(
+            dir.path().join(format!("{}.pid", name)),
+            std::process::id().to_string(),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join(format!("{}.sock", name)), "").unwrap();
+    }
+
+    let names = complete(&["supi", "attach", ""], &[("SUPI_SESSION_DIR", dir.path())]);
+    assert!(
+        names.starts_with(&["api".to_string(), "worker".to_string()]),
+        "Expected session names first: {:?}",
+        names
+    );
+
+    let names = complete(
+        &["supi", "detach", "w"],
+        &[("SUPI_SESSION_DIR", dir.path())],
+    );
+    assert_eq!(names, ["worker"], "Session names by prefix");
+
+    // Completing only reads: no registry is created and leftovers stay
+    std::fs::write(dir.path().join("dead.pid"), "0").unwrap();
+    complete(&["supi", "attach", ""], &[("SUPI_SESSION_DIR", dir.path())]);
+    assert!(
+        dir.path().join("dead.pid").exists(),
+        "Expected leftovers to be kept"
+    );
+
+    let missing = dir.path().join("missing");
+    let names = complete(&["supi", "attach", ""], &[("SUPI_SESSION_DIR", &missing)]);
+    assert!(
+        !names.contains(&"api".to_string()) && !missing.exists(),
+        "Expected no sessions and no registry directory: {:?}",
+        names
+    );
+}
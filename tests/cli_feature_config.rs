@@ -0,0 +1,222 @@
+// Feature: Project and user config files
+// Tests for supi.toml discovery, the user config, --profile and `supi check`
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// A project directory with `supi.toml`, a nested working directory and an empty HOME
+struct Project {
+    root: TempDir,
+}
+
+impl Project {
+    fn new(config: &str) -> Self {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("project/src")).unwrap();
+        std::fs::create_dir_all(root.path().join("home")).unwrap();
+        std::fs::write(root.path().join("project/supi.toml"), config).unwrap();
+        Project { root }
+    }
+
+    fn path(&self, relative: &str) -> std::path::PathBuf {
+        self.root.path().join(relative)
+    }
+
+    fn user_config(&self, config: &str) {
+        let dir = self.path("home/.config/supi");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("config.toml"), config).unwrap();
+    }
+
+    /// supi run from `project/src`, so supi.toml has to be found one level up
+    fn supi(&self) -> Command {
+        let mut cmd = Command::cargo_bin("supi").unwrap();
+        cmd.current_dir(self.path("project/src"))
+            .env("HOME", self.path("home"))
+            .env_remove("XDG_CONFIG_HOME")
+            .timeout(std::time::Duration::from_secs(10));
+        cmd
+    }
+}
+
+fn config_path(project: &Project, relative: &str) -> String {
+    Path::new(&project.path(relative)).display().to_string()
+}
+
+// Manual test: put `command = ["bash", "-c", "echo hi"]` and `stop-on-child-exit = true`
+//              in supi.toml, then run: cargo run --manifest-path <supi>/Cargo.toml
+#[test]
+fn test_supi_toml_is_found_from_subdirectory() {
+    let project = Project::new(
+        r#"
+command = ["bash", "-c", "echo hello from config"]
+stop-on-child-exit = true
+restart_debounce_ms = 250
+"#,
+    );
+
+    project
+        .supi()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "[supi] Config files: {}",
+            config_path(&project, "project/supi.toml")
+        )))
+        .stdout(predicate::str::contains("restart_debounce_ms=250"))
+        .stdout(predicate::str::contains("hello from config"));
+}
+
+// Test that command line flags and a command win over the config file
+#[test]
+fn test_command_line_overrides_config() {
+    let project = Project::new(
+        r#"
+command = "false"
+restart-signal = "SIGHUP"
+stop-on-child-exit = true
+hide = ["secret"]
+"#,
+    );
+
+    project
+        .supi()
+        .args(["--restart-signal", "SIGUSR2", "--hide", "other"])
+        .args(["bash", "--", "-c", "echo secret; echo other"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("restart_signal=SIGUSR2"))
+        .stdout(predicate::str::is_match(r"secret\r?\n").unwrap())
+        .stdout(predicate::str::is_match(r"other\r?\n").unwrap().not());
+}
+
+// Test the precedence of the user config, supi.toml and profiles in `supi check`
+#[test]
+fn test_check_prints_resolved_settings_and_sources() {
+    let project = Project::new(
+        r#"
+command = ["bash", "-c", "true"]
+buffer-lines = 200
+
+[profile.ci]
+log-color = "none"
+"#,
+    );
+    project.user_config(
+        r#"
+buffer-lines = 100
+crash-summary-lines = 5
+
+[profile.ci]
+buffer-lines = 300
+silent = true
+"#,
+    );
+    let project_file = config_path(&project, "project/supi.toml");
+    let user_file = config_path(&project, "home/.config/supi/config.toml");
+
+    project
+        .supi()
+        .arg("check")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "[supi] Config files: {}, {}",
+            user_file, project_file
+        )))
+        .stdout(
+            predicate::str::is_match(format!(
+                r"--buffer-lines\s+200\s+\({}\)",
+                regex::escape(&project_file)
+            ))
+            .unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match(format!(
+                r"--crash-summary-lines\s+5\s+\({}\)",
+                regex::escape(&user_file)
+            ))
+            .unwrap(),
+        )
+        .stdout(predicate::str::is_match(r"--restart-signal\s+SIGUSR1\s+\(default\)").unwrap())
+        .stdout(predicate::str::is_match(r"command\s+bash -c true").unwrap())
+        .stdout(predicate::str::contains("[supi] Config OK"));
+
+    project
+        .supi()
+        .args(["check", "--profile", "ci"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(profile 'ci')"))
+        .stdout(
+            predicate::str::is_match(format!(
+                r"--buffer-lines\s+300\s+\(profile 'ci' in {}\)",
+                regex::escape(&user_file)
+            ))
+            .unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match(format!(
+                r"--log-color\s+none\s+\(profile 'ci' in {}\)",
+                regex::escape(&project_file)
+            ))
+            .unwrap(),
+        );
+
+    project
+        .supi()
+        .args(["check", "--profile", "prod"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Profile 'prod' not found, define it as [profile.prod] in supi.toml (available: ci)",
+        ));
+}
+
+// Test that `supi check` reports config errors with the file they are in
+#[test]
+fn test_check_rejects_invalid_config() {
+    let cases = [
+        ("bogus = 1", "Unknown option 'bogus' in"),
+        (
+            "restart-debounce-ms = \"soon\"",
+            "invalid value 'soon' for '--restart-debounce-ms",
+        ),
+        ("silent = \"yes\"", "Invalid 'silent' in"),
+        ("hide = [", "Invalid config file"),
+        (
+            "command = \"true\"\nlog-color = \"purple\"",
+            "Invalid color 'purple'",
+        ),
+        (
+            "command = \"definitely-not-a-command-supi\"",
+            "Failed to spawn child process",
+        ),
+    ];
+
+    for (config, expected) in cases {
+        let project = Project::new(config);
+        project
+            .supi()
+            .arg("check")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(expected));
+    }
+}
+
+// Test that `supi check` shows only the keys of env vars from the config file
+#[test]
+fn test_check_masks_env_values() {
+    let project = Project::new("command = \"true\"\nenv = [\"API_TOKEN=hunter2\", \"DEBUG=1\"]\n");
+
+    project
+        .supi()
+        .arg("check")
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"--env\s+API_TOKEN=\*\*\*,DEBUG=\*\*\*\s+\(").unwrap())
+        .stdout(predicate::str::contains("hunter2").not());
+}
@@ -0,0 +1,223 @@
+// Feature: Delayed start and start-paused mode
+// Tests for --start-delay and --no-autostart
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::io::Write;
+use std::time::Duration;
+
+// Manual test: cargo run -- --start-delay 5s bash -- -c 'echo started; sleep 30'
+#[test]
+fn test_start_delay_defers_first_start() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--start-delay",
+        "1500ms",
+        "--",
+        "bash",
+        "-c",
+        "echo started; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_millis(700));
+    let early = String::from_utf8_lossy(&output.lock().unwrap()).to_string();
+
+    std::thread::sleep(Duration::from_millis(1800));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        early.contains("[supi] Starting child process in 1s"),
+        "Expected the delay to be logged. Output:\n{}",
+        early
+    );
+    assert!(
+        !early.contains("started\r"),
+        "Expected the child not to start before the delay. Output:\n{}",
+        early
+    );
+    assert_eq!(
+        output_str.matches("started\r").count(),
+        1,
+        "Expected the child to start once after the delay. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Manual test: cargo run -- --no-autostart bash -- -c 'echo started; sleep 30'
+//              (nothing runs until 'r' is pressed)
+#[test]
+fn test_no_autostart_starts_on_restart_hotkey() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--no-autostart",
+        "--",
+        "bash",
+        "-c",
+        "echo started; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+    let before = String::from_utf8_lossy(&output.lock().unwrap()).to_string();
+
+    let mut writer = pair.master.take_writer().unwrap();
+    writer.write_all(b"i").unwrap();
+    writer.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    writer.write_all(b"r").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        before.contains(
+            "[supi] Child process not started (--no-autostart), press hotkey 'r' or send signal(SIGUSR1) to start"
+        ),
+        "Expected a hint on how to start the child. Output:\n{}",
+        before
+    );
+    assert!(
+        !before.contains("started\r"),
+        "Expected the child not to start on its own. Output:\n{}",
+        before
+    );
+    assert!(
+        output_str.contains("[supi] Status: not started"),
+        "Expected the status to show the child was never started. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("started\r"),
+        "Expected the restart hotkey to start the child. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that the restart signal starts a child that wasn't started automatically
+#[test]
+fn test_no_autostart_starts_on_restart_signal() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--no-autostart",
+        "--",
+        "bash",
+        "-c",
+        "echo started; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGUSR1);
+    }
+    std::thread::sleep(Duration::from_secs(1));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("[supi] Child process not running, starting..."),
+        "Expected the signal to start the child. Output:\n{}",
+        output_str
+    );
+    assert_eq!(
+        output_str.matches("started\r").count(),
+        1,
+        "Expected exactly one run. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that a deferred start still rejects a missing command right away
+#[test]
+fn test_deferred_start_validates_command() {
+    for flags in [&["--no-autostart"][..], &["--start-delay", "10s"][..]] {
+        Command::cargo_bin("supi")
+            .unwrap()
+            .args(flags)
+            .args(["--", "definitely-not-a-command-supi"])
+            .timeout(Duration::from_secs(5))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Failed to spawn child process 'definitely-not-a-command-supi'",
+            ))
+            .stderr(predicate::str::contains(
+                "not an executable file or a command in PATH",
+            ));
+    }
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["--start-delay", "later", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --start-delay 'later'"));
+}
+
+// Test that a delay too long to schedule is rejected instead of panicking
+#[test]
+fn test_huge_start_delay_is_rejected() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["--start-delay", "200000000000000d", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid --start-delay '200000000000000d': too large",
+        ))
+        .stderr(predicate::str::contains("panicked").not());
+}
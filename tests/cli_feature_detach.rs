@@ -0,0 +1,351 @@
+// Feature: Daemon mode with detach and attach
+// Tests for --detach, --name, `supi attach` and `supi detach`
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Start a detached session in `dir` and return the supervisor's PID from the pidfile
+fn start_session(dir: &Path, name: &str, script: &str) -> i32 {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .env("SUPI_SESSION_DIR", dir)
+        .args(["--detach", "--name", name, "--", "bash", "-c", script])
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "[supi] Detached session '{}' (supervisor PID",
+            name
+        )));
+
+    std::fs::read_to_string(dir.join(format!("{}.pid", name)))
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+/// Stop a session and wait until its registry entries are gone
+fn stop_session(dir: &Path, name: &str, pid: i32) {
+    unsafe {
+        libc::kill(pid, libc::SIGTERM);
+    }
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while dir.join(format!("{}.pid", name)).exists() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn is_alive(pid: i32) -> bool {
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+// Manual test: cargo run -- --detach --name ticker bash -- -c 'while :; do date; sleep 1; done'
+//              then: supi attach ticker (Ctrl+] to detach), kill <PID> to stop
+#[test]
+fn test_detach_registers_session() {
+    let dir = tempfile::tempdir().unwrap();
+    let pid = start_session(dir.path(), "web", "echo started; sleep 30");
+
+    assert!(is_alive(pid), "Expected the detached supervisor to run");
+    assert!(
+        dir.path().join("web.sock").exists(),
+        "Expected a session socket in the registry"
+    );
+
+    // A second session with the same name is refused
+    Command::cargo_bin("supi")
+        .unwrap()
+        .env("SUPI_SESSION_DIR", dir.path())
+        .args(["--detach", "--name", "web", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Session 'web' is already running (PID {})",
+            pid
+        )));
+
+    stop_session(dir.path(), "web", pid);
+    assert!(
+        !dir.path().join("web.pid").exists() && !dir.path().join("web.sock").exists(),
+        "Expected the registry entries to be removed when the session ends"
+    );
+}
+
+// Test that an attached terminal sees replayed and live output, can use hotkeys and detach
+#[test]
+fn test_attach_streams_output_and_forwards_hotkeys() {
+    let dir = tempfile::tempdir().unwrap();
+    let pid = start_session(dir.path(), "web", "echo started; sleep 30");
+    std::thread::sleep(Duration::from_millis(500));
+
+    let (pair, output, reader_thread) = create_pty_with_reader();
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.env("SUPI_SESSION_DIR", dir.path());
+    cmd.args(["attach", "web"]);
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+    let mut writer = pair.master.take_writer().unwrap();
+    writer.write_all(b"r").unwrap();
+    writer.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(1500));
+
+    // Ctrl+]
+    writer.write_all(&[0x1d]).unwrap();
+    writer.flush().unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut exited = false;
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            exited = true;
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    drop(writer);
+
+    let session_alive = is_alive(pid);
+    stop_session(dir.path(), "web", pid);
+    if !exited {
+        let _ = child.kill();
+    }
+    std::thread::sleep(Duration::from_millis(300));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("[supi] Attached to session 'web'"),
+        "Expected the attach banner. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Hotkey pressed, restarting..."),
+        "Expected the hotkey to reach the supervisor. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.matches("started").count() >= 2,
+        "Expected the replayed first run and the restarted one. Output:\n{}",
+        output_str
+    );
+    assert!(exited, "Expected Ctrl+] to end supi attach");
+    assert!(
+        output_str.contains("[supi] Detached from session 'web', it keeps running"),
+        "Expected the detach message. Output:\n{}",
+        output_str
+    );
+    assert!(
+        session_alive,
+        "Expected the session to keep running after detaching"
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that `supi detach` disconnects attached terminals and leaves the session running
+#[test]
+fn test_detach_command_disconnects_terminals() {
+    let dir = tempfile::tempdir().unwrap();
+    let pid = start_session(dir.path(), "worker", "echo started; sleep 30");
+
+    let (pair, output, reader_thread) = create_pty_with_reader();
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.env("SUPI_SESSION_DIR", dir.path());
+    cmd.args(["attach"]);
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .env("SUPI_SESSION_DIR", dir.path())
+        .args(["detach", "worker"])
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[supi] Detached 1 terminal(s) from session 'worker'",
+        ));
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut exited = false;
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            exited = true;
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    let session_alive = is_alive(pid);
+    stop_session(dir.path(), "worker", pid);
+    if !exited {
+        let _ = child.kill();
+    }
+    std::thread::sleep(Duration::from_millis(300));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(exited, "Expected supi detach to end supi attach");
+    assert!(
+        output_str.contains("[supi] Detached from session 'worker' by 'supi detach'"),
+        "Expected the attached terminal to be told why. Output:\n{}",
+        output_str
+    );
+    assert!(session_alive, "Expected the session to keep running");
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that missing sessions and invalid names are reported clearly
+#[test]
+fn test_session_errors() {
+    let dir = tempfile::tempdir().unwrap();
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .env("SUPI_SESSION_DIR", dir.path())
+        .args(["attach"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No detached supi sessions are running",
+        ));
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .env("SUPI_SESSION_DIR", dir.path())
+        .args(["detach", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "No session named 'nope' is running",
+        ));
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .env("SUPI_SESSION_DIR", dir.path())
+        .args(["--detach", "--name", "a/b", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --name 'a/b'"));
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .env("SUPI_SESSION_DIR", dir.path())
+        .args(["--detach", "--", "definitely-not-a-command-supi"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to spawn child process"));
+}
+
+// Test that a socket still in use is left alone and the host's error is reported,
+// while a stale socket from a host that died is replaced
+#[test]
+fn test_session_socket_in_use_is_not_taken_over() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("web.sock");
+
+    let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+    Command::cargo_bin("supi")
+        .unwrap()
+        .env("SUPI_SESSION_DIR", dir.path())
+        .args(["--detach", "--name", "web", "--", "sleep", "30"])
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to start session 'web'"))
+        .stderr(predicate::str::contains("Session 'web' is already running"));
+    assert!(socket.exists(), "Expected the socket in use to be kept");
+
+    // The socket file stays behind once nothing listens on it
+    drop(listener);
+    let pid = start_session(dir.path(), "web", "sleep 30");
+    stop_session(dir.path(), "web", pid);
+}
+
+// Test that a child command named like a subcommand still runs when it has arguments
+// the subcommand doesn't take
+#[test]
+fn test_subcommand_names_still_run_child_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["attach", "man"] {
+        let script = dir.path().join(name);
+        std::fs::write(&script, format!("#!/bin/sh\necho \"{} child: $*\"\n", name)).unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+    }
+    let path = format!(
+        "{}:{}",
+        dir.path().display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    for (args, expected) in [
+        (&["man", "ls"][..], "man child: ls"),
+        (&["attach", "one", "two"][..], "attach child: one two"),
+        (&["--", "man"][..], "man child: "),
+    ] {
+        Command::cargo_bin("supi")
+            .unwrap()
+            .env("PATH", &path)
+            .env("SUPI_SESSION_DIR", dir.path())
+            .arg("--stop-on-child-exit")
+            .args(args)
+            .timeout(Duration::from_secs(10))
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(expected));
+    }
+}
+
+// Test that an oversized session message only drops that connection, and that a
+// session directory given in $SUPI_SESSION_DIR keeps its mode
+#[test]
+fn test_session_host_rejects_oversized_frames() {
+    use std::io::Read;
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::set_permissions(dir.path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+    let pid = start_session(dir.path(), "guarded", "echo started; sleep 30");
+
+    let mut stream =
+        std::os::unix::net::UnixStream::connect(dir.path().join("guarded.sock")).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    // An attach frame claiming a 4 GiB payload
+    stream.write_all(&[b'a', 0xff, 0xff, 0xff, 0xff]).unwrap();
+    let mut buf = [0u8; 16];
+    assert_eq!(
+        stream.read(&mut buf).unwrap(),
+        0,
+        "Expected the session host to close the connection"
+    );
+    assert!(is_alive(pid), "Expected the session to keep running");
+
+    let mode = std::fs::metadata(dir.path()).unwrap().permissions().mode();
+    assert_eq!(
+        mode & 0o777,
+        0o755,
+        "Expected the session dir mode to be kept"
+    );
+
+    stop_session(dir.path(), "guarded", pid);
+}
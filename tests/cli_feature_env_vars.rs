@@ -0,0 +1,124 @@
+// Feature: SUPI_* environment variables
+// Tests for reading every option from the environment and its precedence
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use tempfile::TempDir;
+
+/// supi in an empty directory with an empty HOME, so no config file is found
+/// unless the test writes one
+fn supi(dir: &TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.current_dir(dir.path())
+        .env("HOME", dir.path())
+        .env_remove("XDG_CONFIG_HOME")
+        .timeout(std::time::Duration::from_secs(10));
+    cmd
+}
+
+// Manual test: SUPI_RESTART_SIGNAL=SIGHUP SUPI_STOP_ON_CHILD_EXIT=1 cargo run -- echo hi
+#[test]
+fn test_env_vars_set_options() {
+    let dir = tempfile::tempdir().unwrap();
+
+    supi(&dir)
+        .env("SUPI_RESTART_SIGNAL", "SIGHUP")
+        .env("SUPI_STOP_ON_CHILD_EXIT", "1")
+        .env("SUPI_HIDE", "secret")
+        .args(["bash", "--", "-c", "echo secret; echo visible"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "restart_signal=SIGHUP (env SUPI_RESTART_SIGNAL)",
+        ))
+        .stdout(predicate::str::contains(
+            "stop_on_child_exit=true (env SUPI_STOP_ON_CHILD_EXIT)",
+        ))
+        .stdout(predicate::str::contains(
+            "restart_debounce_ms=1000 (default)",
+        ))
+        .stdout(predicate::str::contains(
+            "[supi] From config and environment: --hide=secret (env SUPI_HIDE)",
+        ))
+        .stdout(predicate::str::is_match(r"visible\r?\n").unwrap())
+        .stdout(predicate::str::is_match(r"secret\r?\n").unwrap().not());
+}
+
+// Test that --env values from SUPI_ENV are logged as KEY=*** without the secret
+#[test]
+fn test_env_var_values_are_masked() {
+    let dir = tempfile::tempdir().unwrap();
+
+    supi(&dir)
+        .env("SUPI_ENV", "API_TOKEN=hunter2")
+        .args(["--stop-on-child-exit", "true"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "--env=API_TOKEN=*** (env SUPI_ENV)",
+        ))
+        .stdout(predicate::str::contains("hunter2").not());
+}
+
+// Test that command line options win over SUPI_* variables, which win over supi.toml
+#[test]
+fn test_env_var_precedence() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("supi.toml"),
+        "command = \"true\"\nbuffer-lines = 200\ncrash-summary-lines = 5\n",
+    )
+    .unwrap();
+
+    supi(&dir)
+        .env("SUPI_RESTART_SIGNAL", "SIGHUP")
+        .env("SUPI_BUFFER_LINES", "300")
+        .args(["--restart-signal", "SIGUSR2", "--stop-on-child-exit"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "restart_signal=SIGUSR2 (command line)",
+        ))
+        .stdout(predicate::str::contains(
+            "--buffer-lines=300 (env SUPI_BUFFER_LINES)",
+        ))
+        .stdout(predicate::str::is_match(r"--crash-summary-lines=5 \(.*supi\.toml\)").unwrap());
+}
+
+// Test that flags accept the usual boolean spellings and bad values are rejected
+#[test]
+fn test_env_var_values_are_validated() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("supi.toml"), "command = \"true\"\n").unwrap();
+
+    for (value, expected) in [
+        ("yes", "true"),
+        ("1", "true"),
+        ("false", "false"),
+        ("off", "false"),
+    ] {
+        supi(&dir)
+            .env("SUPI_SILENT", value)
+            .arg("check")
+            .assert()
+            .success()
+            .stdout(
+                predicate::str::is_match(format!(r"--silent\s+{}\s+\(env SUPI_SILENT\)", expected))
+                    .unwrap(),
+            );
+    }
+
+    supi(&dir)
+        .env("SUPI_SILENT", "maybe")
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'maybe'"));
+
+    supi(&dir)
+        .env("SUPI_RESTART_DEBOUNCE_MS", "soon")
+        .arg("check")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'soon'"));
+}
@@ -0,0 +1,149 @@
+// Feature: Environment management for the child process
+// Tests for --env, --env-file, --env-command, --clear-env/--keep-env and reloading on restart
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::io::Write;
+use std::time::Duration;
+
+// Manual test: printf 'NAME=world\nGREETING="hello ${NAME}"\n' > /tmp/supi.env
+//              cargo run -- --env-file /tmp/supi.env --env EXTRA=1 bash -- -c 'echo "$GREETING $EXTRA"'
+#[test]
+fn test_env_and_env_file_with_expansion() {
+    let dir = tempfile::tempdir().unwrap();
+    let env_file = dir.path().join(".env");
+    std::fs::write(
+        &env_file,
+        "# app settings\nexport NAME=world\nGREETING=\"hello ${NAME}\"\nLITERAL='$NAME'\nPORT=${SUPI_TEST_UNSET_PORT:-8080} # default\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--stop-on-child-exit",
+        "--env-file",
+        env_file.to_str().unwrap(),
+        "--env",
+        "PORT=9090",
+        "--",
+        "bash",
+        "-c",
+        "echo \"greeting=$GREETING literal=$LITERAL port=$PORT\"",
+    ]);
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "greeting=hello world literal=$NAME port=9090",
+    ));
+}
+
+// Test that --clear-env only passes on the variables in the allow-list
+#[test]
+fn test_clear_env_keeps_allow_list() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.env("SUPI_TEST_SECRET", "hunter2")
+        .env("SUPI_KEEP_ME", "kept")
+        .args([
+            "--stop-on-child-exit",
+            "--clear-env",
+            "--keep-env",
+            "PATH,SUPI_KEEP_*",
+            "--env",
+            "ADDED=yes",
+            "--",
+            "bash",
+            "-c",
+            "echo \"secret=${SUPI_TEST_SECRET:-unset} keep=$SUPI_KEEP_ME added=$ADDED\"",
+        ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("secret=unset keep=kept added=yes"));
+}
+
+// Test that variables printed by --env-command reach the child
+#[test]
+fn test_env_command_output_is_loaded() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--stop-on-child-exit",
+        "--env-command",
+        "echo FROM_COMMAND=direnv; echo SHARED=command",
+        "--env",
+        "SHARED=flag",
+        "--",
+        "bash",
+        "-c",
+        "echo \"from=$FROM_COMMAND shared=$SHARED\"",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("from=direnv shared=flag"));
+}
+
+// Manual test: cargo run -- --env-file /tmp/supi.env bash -- -c 'echo $GREETING; sleep 30'
+//              (edit /tmp/supi.env, press 'r' and the new value is printed)
+#[test]
+fn test_env_file_is_reread_on_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let env_file = dir.path().join(".env");
+    std::fs::write(&env_file, "GREETING=before\n").unwrap();
+
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--env-file",
+        env_file.to_str().unwrap(),
+        "--",
+        "bash",
+        "-c",
+        "echo \"greeting is $GREETING\"; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+    std::fs::write(&env_file, "GREETING=after\n").unwrap();
+
+    let mut writer = pair.master.take_writer().unwrap();
+    writer.write_all(b"r").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+
+    std::thread::sleep(Duration::from_millis(1500));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("greeting is before\r"),
+        "Expected the first run to see the original value. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("Environment reloaded, changed: GREETING"),
+        "Expected the changed variable to be logged. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("greeting is after\r"),
+        "Expected the restarted child to see the edited value. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
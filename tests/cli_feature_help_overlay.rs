@@ -0,0 +1,82 @@
+// Feature: Help overlay listing key bindings and live state
+// Tests for the '?' help panel
+
+mod cli_test_utils;
+
+use cli_test_utils::output_after_typing;
+
+const CHILD: &str = "echo 'child started'; sleep 30";
+
+// Manual test: cargo run -- bash -- -c "sleep 30"
+//              (press '?', a framed help panel should appear)
+#[test]
+fn test_help_panel_lists_bindings_and_state() {
+    let output = output_after_typing(
+        &[
+            "--restart-signal",
+            "SIGHUP",
+            "--restart-debounce-ms",
+            "5000",
+        ],
+        CHILD,
+        &[b"r", b"?"],
+    );
+
+    for expected in [
+        "supi help",
+        "r       restart",
+        "?       help",
+        "ctrl+c  exit supi",
+        "Restart signal: SIGHUP",
+        "Child: running, PID ",
+        "Uptime: ",
+        "Restarts: 1",
+        "Debounce window: 5s (4s left)",
+    ] {
+        assert!(
+            output.contains(expected),
+            "Expected '{}' in help panel. Output:\n{}",
+            expected,
+            output
+        );
+    }
+}
+
+// Test that the panel is shown even in silent mode and reflects a stopped child
+#[test]
+fn test_help_panel_not_suppressed_by_silent() {
+    let output = output_after_typing(&["--silent"], CHILD, &[b"s", b"?"]);
+
+    assert!(
+        output.contains("supi help"),
+        "Expected help panel despite --silent. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Child: stopped"),
+        "Expected stopped child in help panel. Output:\n{}",
+        output
+    );
+}
+
+// Test that the help action can be rebound
+#[test]
+fn test_help_key_can_be_rebound() {
+    let output = output_after_typing(
+        &["--bind", "?=none", "--bind", "f1=help"],
+        CHILD,
+        &[b"?", b"\x1bOP"],
+    );
+
+    assert_eq!(
+        output.matches("supi help").count(),
+        1,
+        "Expected only F1 to open the help panel. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("f1      help"),
+        "Expected rebound key in help panel. Output:\n{}",
+        output
+    );
+}
@@ -0,0 +1,153 @@
+// Feature: Regex-based highlighting of child output
+// Tests for --highlight rules and the built-in presets
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::time::Duration;
+
+// Red in crossterm's 256-color notation, followed by "reset foreground"
+const RED: &str = "\x1b[38;5;9m";
+const YELLOW: &str = "\x1b[38;5;11m";
+const RESET_FG: &str = "\x1b[39m";
+
+// Manual test: cargo run -- --highlight 'boom=red' bash -- -c "echo 'it went boom here'"
+//              (only the word 'boom' should be red)
+#[test]
+fn test_highlight_rule_colors_matched_span() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--silent",
+        "--stop-on-child-exit",
+        "--highlight",
+        "boom=red",
+        "--",
+        "bash",
+        "-c",
+        "echo 'it went boom here'",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    let expected = format!("it went {}boom{} here", RED, RESET_FG);
+    assert!(
+        output_str.contains(&expected),
+        "Expected only 'boom' to be colored red. Output:\n{:?}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that the logs preset colors errors and warnings
+#[test]
+fn test_highlight_preset_logs() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--silent",
+        "--stop-on-child-exit",
+        "--highlight-preset",
+        "logs",
+        "--",
+        "bash",
+        "-c",
+        "echo 'ERROR something broke'; echo 'warning: disk almost full' >&2",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains(&format!("{}ERROR{}", RED, RESET_FG)),
+        "Expected ERROR in red. Output:\n{:?}",
+        output_str
+    );
+    assert!(
+        output_str.contains(&format!("{}warning{}", YELLOW, RESET_FG)),
+        "Expected warning in yellow on stderr too. Output:\n{:?}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that child output which already contains colors is forwarded verbatim
+#[test]
+fn test_highlight_leaves_colored_lines_untouched() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--silent",
+        "--stop-on-child-exit",
+        "--highlight-preset",
+        "logs",
+        "--",
+        "bash",
+        "-c",
+        "printf '\\033[32mgreen error\\033[0m\\n'",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("\x1b[32mgreen error\x1b[0m"),
+        "Expected pre-colored line to be forwarded verbatim. Output:\n{:?}",
+        output_str
+    );
+    assert!(
+        !output_str.contains(RED),
+        "Expected no highlight colors in pre-colored line. Output:\n{:?}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that invalid highlight rules and presets are rejected
+#[test]
+fn test_invalid_highlight_is_rejected() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--highlight", "error=purple", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid color"));
+
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--highlight-preset", "fancy", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --highlight-preset"));
+}
@@ -0,0 +1,195 @@
+// Feature: Init mode for running as PID 1 in containers
+// Tests for --subreaper: orphan reaping, default signal forwarding and exit status propagation
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::time::Duration;
+
+// Manual test: cargo run -- --subreaper --stop-on-child-exit bash -- -c "(sleep 0.5 &); sleep 2; ps -o pid,stat --ppid \$PPID"
+//              (the orphaned sleep is reaped and doesn't show up as a zombie)
+#[test]
+fn test_subreaper_reaps_orphaned_grandchildren() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--subreaper",
+        "--stop-on-child-exit",
+        "--",
+        "bash",
+        "-c",
+        // The subshell exits right away, so the sleep is orphaned and re-parented to supi
+        "(sleep 0.3 &); sleep 1.5; \
+         if ps -o stat= --ppid $PPID | grep -q Z; then echo 'zombie found'; else echo 'no zombies'; fi",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("Init mode: child subreaper enabled"),
+        "Expected init mode to be announced. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("Reaped orphaned process ") && output_str.contains("(exit code 0)"),
+        "Expected the orphan to be reaped. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("no zombies\r"),
+        "Expected no zombie children of supi. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that init mode exits with the child's exit code
+#[test]
+fn test_init_mode_exits_with_child_status() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--subreaper",
+        "--stop-on-child-exit",
+        "--",
+        "bash",
+        "-c",
+        "exit 3",
+    ]);
+
+    cmd.assert()
+        .code(3)
+        .stdout(predicate::str::contains("Exiting with child exit code 3"));
+}
+
+// Manual test: cargo run -- --subreaper bash -- -c "trap 'echo hup' HUP; while true; do sleep 1; done"
+//              (kill -HUP <supi pid> prints 'hup' without restarting the child)
+#[test]
+fn test_init_mode_forwards_sighup_by_default() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--subreaper",
+        "--",
+        "bash",
+        "-c",
+        "echo 'child started'; trap 'echo got hangup' HUP; while true; do sleep 0.1; done",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGHUP);
+    }
+    std::thread::sleep(Duration::from_millis(700));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("got hangup\r"),
+        "Expected SIGHUP to be forwarded to the child. Output:\n{}",
+        output_str
+    );
+    assert_eq!(
+        output_str.matches("child started\r").count(),
+        1,
+        "Expected forwarding not to restart the child. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that stopping supi with SIGTERM in init mode exits with the code the child chose
+#[test]
+fn test_init_mode_sigterm_exits_with_child_status() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--subreaper",
+        "--",
+        "bash",
+        "-c",
+        "trap 'echo cleaning up; exit 7' TERM; while true; do sleep 0.1; done",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let status = child.wait().unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert_eq!(
+        status.exit_code(),
+        7,
+        "Expected supi to exit with the child's exit code. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("cleaning up\r"),
+        "Expected the child to handle SIGTERM. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that the reaper leaves trigger commands to supi, so their exit status is reported
+#[test]
+fn test_init_mode_keeps_trigger_command_status() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--subreaper",
+        "--stop-on-child-exit",
+        "--trigger",
+        "run(sleep 0.1; exit 3):tick",
+        "--",
+        "bash",
+        "-c",
+        "for i in 1 2 3; do echo tick; sleep 0.5; done",
+    ])
+    .timeout(Duration::from_secs(10))
+    .assert()
+    .success()
+    .stderr(
+        predicate::str::contains("Trigger command 'sleep 0.1; exit 3' exited with status").count(3),
+    )
+    .stderr(predicate::str::contains("Failed to run trigger command").not())
+    .stdout(predicate::str::contains("Reaped orphaned process").not());
+}
@@ -0,0 +1,176 @@
+// Feature: Configurable keymap with multiple hotkey actions
+// Tests for --bind, the default bindings and the bindings hint
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::io::Write;
+use std::time::Duration;
+
+// Manual test: cargo run -- --bind x=restart bash -- -c "echo started; sleep 30"
+//              (press 'x', 'started' should be printed again)
+#[test]
+fn test_bind_custom_restart_key() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--bind",
+        "x=restart",
+        "--",
+        "bash",
+        "-c",
+        "echo 'child started'; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    let mut writer = pair.master.take_writer().unwrap();
+    writer.write_all(b"x").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("x=restart"),
+        "Expected the custom binding in the hotkey hint. Output:\n{}",
+        output_str
+    );
+    assert_eq!(
+        output_str.matches("child started\r").count(),
+        2,
+        "Expected the child to be restarted by 'x'. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Manual test: cargo run -- bash -- -c "echo started; sleep 30"
+//              (press 's', then 'r' - the child should stop and start again)
+#[test]
+fn test_stop_key_keeps_supervisor_running() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args(["--", "bash", "-c", "echo 'child started'; sleep 30"]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    let mut writer = pair.master.take_writer().unwrap();
+    writer.write_all(b"s").unwrap();
+    writer.flush().unwrap();
+    std::thread::sleep(Duration::from_secs(1));
+
+    writer.write_all(b"i").unwrap();
+    writer.flush().unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+
+    writer.write_all(b"r").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+    std::thread::sleep(Duration::from_secs(1));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("Status: stopped"),
+        "Expected status to report the stopped child. Output:\n{}",
+        output_str
+    );
+    assert_eq!(
+        output_str.matches("child started\r").count(),
+        2,
+        "Expected the child to be started again after stop. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that the default 'q' binding shuts supi down cleanly
+#[test]
+fn test_quit_key_exits() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args(["--", "bash", "-c", "sleep 30"]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    let mut writer = pair.master.take_writer().unwrap();
+    writer.write_all(b"q").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+
+    let status = child.wait().unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        status.success(),
+        "Expected exit code 0 after quit. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Quit requested, shutting down..."),
+        "Expected quit message. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that invalid bindings are rejected at startup
+#[test]
+fn test_invalid_bind_is_rejected() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--bind", "x=explode", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid action"));
+
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--restart-hotkey", "d", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is already used for"));
+}
@@ -0,0 +1,113 @@
+// Feature: Working directory, umask and user/group for the child
+// Tests for --cwd, --umask, --user and --group
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::os::unix::fs::PermissionsExt;
+
+// Manual test: cargo run -- --stop-on-child-exit --cwd /tmp --umask 077 bash -- -c 'pwd; umask'
+#[test]
+fn test_cwd_and_umask_apply_to_child() {
+    let dir = tempfile::tempdir().unwrap();
+    let cwd = dir.path().canonicalize().unwrap();
+
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--stop-on-child-exit",
+        "--cwd",
+        cwd.to_str().unwrap(),
+        "--umask",
+        "077",
+        "--",
+        "bash",
+        "-c",
+        "echo \"pwd=$(pwd -P) umask=$(umask)\"; touch created",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "pwd={} umask=0077",
+            cwd.display()
+        )));
+
+    let mode = std::fs::metadata(cwd.join("created"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(
+        mode & 0o777,
+        0o600,
+        "Expected umask 077 to apply to new files"
+    );
+}
+
+// Test that invalid --cwd and --umask values are rejected before the child starts
+#[test]
+fn test_invalid_cwd_and_umask_are_rejected() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["--cwd", "/definitely/not/a/dir", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid --cwd '/definitely/not/a/dir': not a directory",
+        ));
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["--umask", "0999", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --umask '0999'"));
+}
+
+// Manual test: sudo cargo run -- --stop-on-child-exit --user nobody bash -- -c 'id; echo $HOME'
+//              (without root, supi refuses to start)
+#[test]
+fn test_user_switch_requires_root() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--stop-on-child-exit",
+        "--user",
+        "nobody",
+        "--",
+        "bash",
+        "-c",
+        "echo \"user=$(id -un) uid=$(id -u) home=$HOME logname=$LOGNAME\"",
+    ]);
+
+    if unsafe { libc::geteuid() } == 0 {
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::contains("user=nobody uid=65534"))
+            .stdout(predicate::str::contains("logname=nobody"))
+            .stdout(predicate::str::contains("user=nobody (uid 65534"));
+    } else {
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("supi needs to run as root"));
+    }
+}
+
+// Test that unknown users and groups are reported clearly
+#[test]
+fn test_unknown_user_and_group_are_rejected() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["--user", "no-such-user-supi", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid --user 'no-such-user-supi': no such user",
+        ));
+
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["--group", "no-such-group-supi", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid --group 'no-such-group-supi': no such group",
+        ));
+}
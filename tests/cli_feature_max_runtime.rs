@@ -0,0 +1,150 @@
+// Feature: Maximum runtime per run
+// Tests for --max-runtime with restarts and with --stop-on-child-exit
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::time::Duration;
+
+// Manual test: cargo run -- --max-runtime 5s bash -- -c 'echo started; sleep 30'
+#[test]
+fn test_max_runtime_restarts_child() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--max-runtime",
+        "1s",
+        "--",
+        "bash",
+        "-c",
+        "echo started; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_millis(2800));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("[supi] Max runtime: 1s per run, then restart"),
+        "Expected the max runtime in the banner. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Run #1 reached the maximum runtime of 1s, stopping..."),
+        "Expected the first run to be stopped. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Run #2 reached the maximum runtime of 1s, stopping..."),
+        "Expected the deadline to restart with every run. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.matches("started\r").count() >= 3,
+        "Expected the child to be restarted after each run. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that --stop-on-child-exit turns an exceeded max runtime into exit code 124
+#[test]
+fn test_max_runtime_exits_with_timeout_code() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args([
+            "--stop-on-child-exit",
+            "--max-runtime",
+            "500ms",
+            "--",
+            "bash",
+            "-c",
+            "echo started; sleep 30",
+        ])
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .code(124)
+        .stdout(predicate::str::contains(
+            "[supi] Exiting with code 124 (--max-runtime exceeded, --stop-on-child-exit is set)",
+        ))
+        .stderr(predicate::str::contains(
+            "[supi] Run #1 reached the maximum runtime of 500ms, stopping...",
+        ));
+}
+
+// Test that a child finishing within its max runtime exits normally
+#[test]
+fn test_child_within_max_runtime_exits_normally() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args([
+            "--stop-on-child-exit",
+            "--max-runtime",
+            "10s",
+            "--",
+            "bash",
+            "-c",
+            "echo done",
+        ])
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[supi] Exiting (--stop-on-child-exit is set)",
+        ))
+        .stderr(predicate::str::contains("maximum runtime").not());
+}
+
+// Test that invalid durations are rejected before the child starts
+#[test]
+fn test_invalid_max_runtime_is_rejected() {
+    for value in ["soon", "0s", "99999999999999999999999d"] {
+        Command::cargo_bin("supi")
+            .unwrap()
+            .args(["--max-runtime", value, "--", "true"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "Invalid --max-runtime '{}'",
+                value
+            )));
+    }
+}
+
+// Test that a max runtime beyond any representable time never ends the run
+#[test]
+fn test_huge_max_runtime_never_expires() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args([
+            "--stop-on-child-exit",
+            "--max-runtime",
+            "200000000000000d",
+            "--",
+            "bash",
+            "-c",
+            "echo done",
+        ])
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("done"))
+        .stderr(predicate::str::contains("panicked").not());
+}
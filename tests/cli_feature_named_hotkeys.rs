@@ -0,0 +1,98 @@
+// Feature: Named keys, modifiers and leader-key sequences for hotkeys
+// Tests for --restart-hotkey / --bind with ctrl+<key>, function keys and "space r" style sequences
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::output_after_typing;
+use predicates::prelude::*;
+
+/// Start supi with the given hotkey args, type the given inputs and count child starts
+fn count_starts_after_typing(hotkey_args: &[&str], inputs: &[&[u8]]) -> (usize, String) {
+    let mut supi_args = hotkey_args.to_vec();
+    supi_args.extend(["--restart-debounce-ms", "0"]);
+    let output = output_after_typing(&supi_args, "echo 'child started'; sleep 30", inputs);
+    (output.matches("child started\r").count(), output)
+}
+
+// Manual test: cargo run -- --restart-hotkey ctrl+r bash -- -c "echo started; sleep 30"
+//              (plain 'r' does nothing, Ctrl+R restarts)
+#[test]
+fn test_ctrl_modifier_hotkey() {
+    // 0x12 is what the terminal sends for Ctrl+R
+    let (starts, output) =
+        count_starts_after_typing(&["--restart-hotkey", "ctrl+r"], &[b"r", b"\x12"]);
+
+    assert!(
+        output.contains("ctrl+r=restart"),
+        "Expected the named key in the bindings hint. Output:\n{}",
+        output
+    );
+    assert_eq!(
+        starts, 2,
+        "Expected only Ctrl+R to restart, not plain 'r'. Output:\n{}",
+        output
+    );
+}
+
+// Test that function keys can be bound with --bind
+#[test]
+fn test_function_key_binding() {
+    // ESC [ 1 5 ~ is the xterm sequence for F5
+    let (starts, output) = count_starts_after_typing(&["--bind", "f5=restart"], &[b"\x1b[15~"]);
+
+    assert_eq!(
+        starts, 2,
+        "Expected F5 to restart the child. Output:\n{}",
+        output
+    );
+}
+
+// Manual test: cargo run -- --restart-hotkey "space r" bash -- -c "echo started; sleep 30"
+//              (press space, then r within 1.5 seconds)
+#[test]
+fn test_leader_sequence_hotkey() {
+    let (starts, output) = count_starts_after_typing(
+        &["--restart-hotkey", "space r"],
+        &[b"r", b"x", b"r", b" ", b"r"],
+    );
+
+    assert!(
+        output.contains("space r=restart"),
+        "Expected the sequence in the bindings hint. Output:\n{}",
+        output
+    );
+    assert_eq!(
+        starts, 2,
+        "Expected only the complete 'space r' sequence to restart. Output:\n{}",
+        output
+    );
+}
+
+// Test that unsupported key combinations are rejected with a clear error
+#[test]
+fn test_invalid_key_specs_are_rejected() {
+    let cases = [
+        ("ctrl+c", "reserved for exiting supi"),
+        ("ctrl+shift+r", "cannot distinguish"),
+        ("super+r", "unknown modifier 'super'"),
+        ("f13", "unknown key 'f13'"),
+    ];
+
+    for (spec, message) in cases {
+        let mut cmd = Command::cargo_bin("supi").unwrap();
+        cmd.args(["--restart-hotkey", spec, "echo", "test"])
+            .timeout(std::time::Duration::from_secs(2))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(message));
+    }
+
+    // A key that also starts a leader sequence could never complete it
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--bind", "r r=stop", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is a prefix of 'r r'"));
+}
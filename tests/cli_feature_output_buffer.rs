@@ -0,0 +1,221 @@
+// Feature: In-memory output ring buffer
+// Tests for dumping buffered child output and the crash summary block
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::io::Write;
+use std::time::Duration;
+
+// Manual test: cargo run -- --stop-on-child-exit bash -- -c "echo err1 >&2; echo err2 >&2; exit 3"
+//              (should print a summary block with both stderr lines)
+#[test]
+fn test_crash_summary_shows_last_stderr_lines() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--stop-on-child-exit",
+        "--",
+        "bash",
+        "-c",
+        "echo 'first error' >&2; echo 'not an error'; echo 'second error' >&2; exit 3",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("last 2 stderr lines of run #1"),
+        "Expected crash summary header. Output:\n{}",
+        output_str
+    );
+
+    // The summary block repeats the stderr lines but not the stdout ones
+    let summary = &output_str[output_str.find("stderr lines of run").unwrap()..];
+    assert!(
+        summary.contains("first error") && summary.contains("second error"),
+        "Expected stderr lines in the summary. Output:\n{}",
+        output_str
+    );
+    assert!(
+        !summary.contains("not an error"),
+        "Expected stdout lines to be left out of the summary. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that a successful exit doesn't print a crash summary
+#[test]
+fn test_no_crash_summary_on_success() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--stop-on-child-exit",
+        "--",
+        "bash",
+        "-c",
+        "echo 'warning only' >&2; exit 0",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        !output_str.contains("stderr lines of run"),
+        "Expected no crash summary on success. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Manual test: cargo run -- --dump-signal SIGUSR2 bash -- -c "echo hello; echo oops >&2; sleep 30"
+//              (then in another terminal: kill -USR2 <pid>, check supi-output.log)
+#[test]
+fn test_dump_signal_writes_buffer_to_file() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+    let dump_dir = tempfile::tempdir().unwrap();
+    let dump_file = dump_dir.path().join("dump.log");
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--dump-signal",
+        "SIGUSR2",
+        "--dump-file",
+        dump_file.to_str().unwrap(),
+        "--",
+        "bash",
+        "-c",
+        "echo 'hello stdout'; echo 'hello stderr' >&2; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGUSR2);
+    }
+    std::thread::sleep(Duration::from_millis(500));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("Dumped 2 buffered lines"),
+        "Expected dump confirmation. Output:\n{}",
+        output_str
+    );
+
+    let dump = std::fs::read_to_string(&dump_file).expect("Dump file should exist");
+    assert!(
+        dump.contains("[run 1] [stdout] hello stdout"),
+        "Expected tagged stdout line in dump. Dump:\n{}",
+        dump
+    );
+    assert!(
+        dump.contains("[run 1] [stderr] hello stderr"),
+        "Expected tagged stderr line in dump. Dump:\n{}",
+        dump
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Manual test: cargo run -- --buffer-lines 2 bash -- -c "for i in 1 2 3 4 5; do echo line$i; done; sleep 30"
+//              (then press 'd', supi-output.log should only contain line4 and line5)
+#[test]
+fn test_dump_hotkey_respects_buffer_line_limit() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+    let dump_dir = tempfile::tempdir().unwrap();
+    let dump_file = dump_dir.path().join("dump.log");
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--buffer-lines",
+        "2",
+        "--dump-file",
+        dump_file.to_str().unwrap(),
+        "--",
+        "bash",
+        "-c",
+        "for i in 1 2 3 4 5; do echo line$i; done; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    // Send default 'd' dump hotkey through PTY master
+    let mut writer = pair.master.take_writer().unwrap();
+    writer.write_all(b"d").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+
+    std::thread::sleep(Duration::from_millis(500));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let dump = std::fs::read_to_string(&dump_file).expect("Dump file should exist");
+    assert!(
+        !dump.contains("line3"),
+        "Expected older lines to be evicted. Dump:\n{}",
+        dump
+    );
+    assert!(
+        dump.contains("line4") && dump.contains("line5"),
+        "Expected the last two lines in the dump. Dump:\n{}",
+        dump
+    );
+
+    drop(output);
+    let _ = reader_thread.join();
+}
+
+// Test that a buffer size that doesn't fit in memory is rejected instead of overflowing
+#[test]
+fn test_oversized_buffer_is_rejected() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["--buffer-mb", &u64::MAX.to_string(), "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --buffer-mb"))
+        .stderr(predicate::str::contains("panicked").not());
+}
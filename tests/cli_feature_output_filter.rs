@@ -0,0 +1,217 @@
+// Feature: Static include/exclude filters for child output
+// Tests for --hide, --only, the filter toggle hotkey and hidden line counts
+
+mod cli_test_utils;
+
+use cli_test_utils::create_pty_with_reader;
+use portable_pty::CommandBuilder;
+use std::io::Write;
+use std::time::Duration;
+
+// Manual test: cargo run -- --hide progress bash -- -c "echo 'progress 1'; echo 'real line'; echo 'progress 2'"
+//              (only 'real line' should be visible, plus a hidden line count)
+#[test]
+fn test_hide_suppresses_matching_lines() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--stop-on-child-exit",
+        "--hide",
+        "^progress",
+        "--",
+        "bash",
+        "-c",
+        "echo 'progress 1'; echo 'real line'; echo 'progress 2'",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("real line\r"),
+        "Expected unfiltered line to be visible. Output:\n{}",
+        output_str
+    );
+    assert!(
+        !output_str.contains("progress 1\r") && !output_str.contains("progress 2\r"),
+        "Expected hidden lines to be suppressed. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("2 lines hidden by output filters during run #1"),
+        "Expected hidden line count in exit summary. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that stream prefixes restrict --only to a single stream
+#[test]
+fn test_only_filter_per_stream() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--stop-on-child-exit",
+        "--only",
+        "stderr:ERROR",
+        "--",
+        "bash",
+        "-c",
+        "echo 'regular stdout'; echo 'debug chatter' >&2; echo 'ERROR real problem' >&2",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("regular stdout\r"),
+        "Expected stdout to be unaffected by a stderr-only filter. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("ERROR real problem\r"),
+        "Expected matching stderr line to be visible. Output:\n{}",
+        output_str
+    );
+    assert!(
+        !output_str.contains("debug chatter\r"),
+        "Expected non-matching stderr line to be hidden. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Manual test: cargo run -- --hide noise bash -- -c "sleep 2; echo noise; sleep 30"
+//              (press 'f' within 2 seconds, 'noise' should be visible)
+#[test]
+fn test_filter_hotkey_toggles_filtering() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--hide",
+        "noise",
+        "--",
+        "bash",
+        "-c",
+        "echo 'noise before toggle'; sleep 2; echo 'noise after toggle'; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    // Send default 'f' filter hotkey through PTY master
+    let mut writer = pair.master.take_writer().unwrap();
+    writer.write_all(b"f").unwrap();
+    writer.flush().unwrap();
+    drop(writer);
+
+    std::thread::sleep(Duration::from_secs(2));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        !output_str.contains("noise before toggle\r"),
+        "Expected line before toggle to be hidden. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("Output filters disabled"),
+        "Expected toggle confirmation. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("noise after toggle\r"),
+        "Expected line after toggle to be visible. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that hidden lines are still recorded in the output buffer
+#[test]
+fn test_hidden_lines_still_reach_output_buffer() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+    let dump_dir = tempfile::tempdir().unwrap();
+    let dump_file = dump_dir.path().join("dump.log");
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--hide",
+        "healthcheck",
+        "--dump-signal",
+        "SIGUSR2",
+        "--dump-file",
+        dump_file.to_str().unwrap(),
+        "--",
+        "bash",
+        "-c",
+        "echo 'GET /healthcheck 200'; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(1));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGUSR2);
+    }
+    std::thread::sleep(Duration::from_millis(500));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("1 lines hidden by output filters in total"),
+        "Expected total hidden count on exit. Output:\n{}",
+        output_str
+    );
+
+    let dump = std::fs::read_to_string(&dump_file).expect("Dump file should exist");
+    assert!(
+        dump.contains("GET /healthcheck 200"),
+        "Expected hidden line in the dump. Dump:\n{}",
+        dump
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
@@ -0,0 +1,192 @@
+// Feature: Output pattern triggers
+// Tests for running actions when child output matches a regex
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::{create_pty_with_reader, run_supi};
+use portable_pty::CommandBuilder;
+use predicates::prelude::*;
+use std::time::Duration;
+
+// Manual test: cargo run -- --trigger 'exit(7):All tests passed' bash -- -c "echo 'All tests passed'; sleep 30"
+//              (supi should exit with code 7 right after the line is printed)
+#[test]
+fn test_exit_trigger_exits_with_code() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--trigger",
+        "exit(7),stdout:All tests passed",
+        "--",
+        "bash",
+        "-c",
+        "echo 'All tests passed'; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let status = child.wait().unwrap();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    assert!(
+        output_str.contains("Trigger /All tests passed/ matched on stdout"),
+        "Expected trigger log message. Output:\n{}",
+        output_str
+    );
+    assert_eq!(
+        status.exit_code(),
+        7,
+        "Expected supi to exit with the trigger's exit code. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Manual test: cargo run -- --trigger 'restart,max=1:EADDRINUSE' bash -- -c "echo started; echo EADDRINUSE >&2; sleep 30"
+//              (should restart exactly once, the second match is over the max)
+#[test]
+fn test_restart_trigger_respects_max_matches() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--restart-debounce-ms",
+        "0",
+        "--trigger",
+        "restart,stderr,max=1:EADDRINUSE",
+        "--",
+        "bash",
+        "-c",
+        "echo 'Process started'; echo 'Error: listen EADDRINUSE' >&2; sleep 30",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let supi_pid = child.process_id().unwrap();
+    drop(pair.slave);
+
+    std::thread::sleep(Duration::from_secs(3));
+
+    unsafe {
+        libc::kill(supi_pid as i32, libc::SIGTERM);
+    }
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    let started_count = output_str.matches("Process started\r").count();
+    assert_eq!(
+        started_count, 2,
+        "Expected exactly one trigger restart. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that the notify action prints the matched line as a highlighted banner
+#[test]
+fn test_notify_trigger_prints_banner() {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args([
+        "--stop-on-child-exit",
+        "--trigger",
+        "notify:^FATAL",
+        "--",
+        "bash",
+        "-c",
+        "echo 'FATAL: out of memory'; sleep 1",
+    ]);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    drop(pair.slave);
+
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_bytes = output.lock().unwrap();
+    let output_str = String::from_utf8_lossy(&output_bytes);
+
+    // Reverse video (ESC[7m) is used for the banner
+    assert!(
+        output_str.contains("\x1b[7m"),
+        "Expected reverse video banner. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.matches("FATAL: out of memory").count() >= 2,
+        "Expected the matched line to be repeated in the banner. Output:\n{}",
+        output_str
+    );
+
+    drop(output_bytes);
+    let _ = reader_thread.join();
+}
+
+// Test that invalid trigger specs are rejected before anything is spawned
+#[test]
+fn test_invalid_trigger_is_rejected() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--trigger", "explode:boom", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unsupported action"));
+
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--trigger", "restart", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected <action>:<regex>"));
+
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--trigger",
+        "restart,cooldown=99999999999999999999999d:x",
+        "echo",
+        "test",
+    ])
+    .timeout(std::time::Duration::from_secs(2))
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("too large"));
+}
+
+// Manual test: cargo run -- --hide noise --trigger stop:ready -- bash -c "echo noise; echo ready; sleep 30"
+//              (the hidden line count for run #1 should be reported when the trigger stops it)
+#[test]
+fn test_stop_trigger_reports_hidden_lines() {
+    let output_str = run_supi(
+        &[
+            "--hide",
+            "noise",
+            "--trigger",
+            "stop:ready",
+            "--",
+            "bash",
+            "-c",
+            "echo noise; echo ready; sleep 30",
+        ],
+        Duration::from_secs(2),
+        |_| {},
+    );
+
+    assert!(
+        output_str.contains("1 lines hidden by output filters during run #1"),
+        "Expected the hidden line count when the trigger stopped the run. Output:\n{}",
+        output_str
+    );
+}
@@ -0,0 +1,129 @@
+// Feature: Pause and resume the child process group (SIGSTOP/SIGCONT)
+// Tests for the pause hotkey, :pause/:resume commands and shutdown of a paused child
+
+mod cli_test_utils;
+
+use cli_test_utils::run_supi;
+use std::time::Duration;
+
+const TICKER: &str =
+    "echo 'child started'; i=0; while true; do i=$((i+1)); echo \"tick $i\"; sleep 0.1; done";
+
+/// Start supi with the given child script and type the inputs, each followed by
+/// its own pause in milliseconds
+fn output_after_typing(script: &str, inputs: &[(&[u8], u64)]) -> String {
+    run_supi(
+        &["--", "bash", "-c", script],
+        Duration::from_secs(1),
+        |supi| {
+            for (input, wait_ms) in inputs {
+                supi.type_keys(input);
+                std::thread::sleep(Duration::from_millis(*wait_ms));
+            }
+        },
+    )
+}
+
+/// Number of "tick N" lines printed between two markers in the output
+fn ticks_between(output: &str, start: &str, end: &str) -> usize {
+    let start = output.find(start).expect("start marker in output");
+    let end = start + output[start..].find(end).expect("end marker in output");
+    output[start..end].matches("tick ").count()
+}
+
+// Manual test: cargo run -- bash -- -c "while true; do date; sleep 0.5; done"
+//              (press 'p', output stops and a PAUSED line appears; press 'p' again)
+#[test]
+fn test_pause_hotkey_freezes_and_resumes_child() {
+    let output = output_after_typing(TICKER, &[(b"p", 1500), (b"p", 1000)]);
+
+    assert!(
+        output.contains("PAUSED: child process group stopped"),
+        "Expected the paused status line. Output:\n{}",
+        output
+    );
+    // A line that was already in the pipe when the group stopped may still be forwarded
+    assert!(
+        ticks_between(&output, "paused (SIGSTOP)", "resumed (SIGCONT)") <= 1,
+        "Expected no child output while paused. Output:\n{}",
+        output
+    );
+    assert!(
+        ticks_between(&output, "resumed (SIGCONT)", "Received SIGTERM") > 3,
+        "Expected child output to continue after resume. Output:\n{}",
+        output
+    );
+}
+
+// Test that a paused child is continued before SIGTERM so it can exit gracefully
+#[test]
+fn test_shutdown_continues_paused_child() {
+    let output = output_after_typing(
+        "trap 'echo got term; exit 0' TERM; while true; do sleep 0.1; done",
+        &[(b"p", 500)],
+    );
+
+    assert!(
+        output.contains("Resuming paused child process group before stopping"),
+        "Expected SIGCONT before SIGTERM. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("got term\r"),
+        "Expected the child to handle SIGTERM. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Child process stopped gracefully"),
+        "Expected a graceful stop instead of a force kill. Output:\n{}",
+        output
+    );
+}
+
+// Test the explicit :pause and :resume palette commands and the status output
+#[test]
+fn test_pause_resume_commands() {
+    let output = output_after_typing(
+        TICKER,
+        &[
+            (b":pause\r", 300),
+            (b"i", 300),
+            (b":pause\r", 300),
+            (b":resume\r", 300),
+        ],
+    );
+
+    assert!(
+        output.contains("Status: paused (PID: "),
+        "Expected status to report the paused child. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Child process is already paused"),
+        "Expected :pause not to toggle. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Child process group resumed (SIGCONT)"),
+        "Expected :resume to continue the child. Output:\n{}",
+        output
+    );
+}
+
+// Test that restarting a paused child works
+#[test]
+fn test_restart_while_paused() {
+    let output = output_after_typing(TICKER, &[(b"p", 300), (b"r", 1000)]);
+
+    assert_eq!(
+        output.matches("child started\r").count(),
+        2,
+        "Expected the paused child to be restarted. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Child process stopped gracefully"),
+        "Expected the paused child to stop gracefully. Output:\n{}",
+        output
+    );
+}
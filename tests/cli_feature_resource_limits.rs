@@ -0,0 +1,109 @@
+// Feature: Resource limits, niceness and CPU affinity for the child
+// Tests for --limit, --nice, --ionice, --cpu-affinity and the limits banner
+
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+// Manual test: cargo run -- --stop-on-child-exit --limit nofile=512,as=2G bash -- -c 'ulimit -n; ulimit -v'
+#[test]
+fn test_limits_are_applied_and_shown_in_banner() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--stop-on-child-exit",
+        "--limit",
+        "nofile=512,as=2G",
+        "--limit",
+        "fsize=1M:2M",
+        "--",
+        "bash",
+        "-c",
+        "echo \"nofile=$(ulimit -n) as=$(ulimit -v) fsize=$(ulimit -Sf):$(ulimit -Hf)\"",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[supi] Limits: nofile=512, as=2G, fsize=1M:2M",
+        ))
+        // bash reports -v and -f in KiB
+        .stdout(predicate::str::contains(
+            "nofile=512 as=2097152 fsize=1024:2048",
+        ));
+}
+
+// Test that the nice value and CPU affinity reach the child
+#[test]
+fn test_nice_and_cpu_affinity_are_applied() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--stop-on-child-exit",
+        "--nice",
+        "7",
+        "--ionice",
+        "idle",
+        "--cpu-affinity",
+        "0",
+        "--",
+        "bash",
+        "-c",
+        "echo \"nice=$(nice) $(grep Cpus_allowed_list /proc/self/status | tr -d '\\t')\"",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "[supi] Limits: nice=7, ionice=idle, cpu-affinity=0",
+        ))
+        .stdout(predicate::str::contains("nice=7 Cpus_allowed_list:0"));
+}
+
+// Test that size and CPU time values accept units and zero
+#[test]
+fn test_unlimited_and_duration_values() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args([
+        "--stop-on-child-exit",
+        "--limit",
+        "core=0,cpu=10m",
+        "--",
+        "bash",
+        "-c",
+        "echo \"core=$(ulimit -c) cpu=$(ulimit -t)\"",
+    ]);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("core=0 cpu=600"));
+}
+
+// Test that invalid specs are rejected with a clear error before the child starts
+#[test]
+fn test_invalid_limit_specs_are_rejected() {
+    let cases: [(&[&str], &str); 6] = [
+        (&["--limit", "files=10"], "unknown resource 'files'"),
+        (&["--limit", "nofile"], "expected NAME=VALUE"),
+        (
+            &["--limit", "nofile=20:10"],
+            "soft limit is above the hard limit",
+        ),
+        (
+            &["--nice", "42"],
+            "Invalid --nice '42': must be between -20 and 19",
+        ),
+        (&["--cpu-affinity", "3-1"], "range 3-1 is backwards"),
+        (
+            &["--cpu-affinity", "0-999999999999"],
+            "CPU 999999999999 is out of range, the maximum is 1023",
+        ),
+    ];
+
+    for (args, expected) in cases {
+        Command::cargo_bin("supi")
+            .unwrap()
+            .args(args)
+            .args(["--", "true"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(expected));
+    }
+}
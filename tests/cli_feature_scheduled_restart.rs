@@ -0,0 +1,163 @@
+// Feature: Scheduled restarts
+// Tests for --restart-every and --restart-cron
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::run_supi;
+use predicates::prelude::*;
+use std::time::Duration;
+
+// Manual test: cargo run -- --restart-every 10s bash -- -c 'echo started; sleep 60'
+#[test]
+fn test_restart_every_restarts_child() {
+    let output_str = run_supi(
+        &[
+            "--restart-every",
+            "1s",
+            "--restart-debounce-ms",
+            "0",
+            "--",
+            "bash",
+            "-c",
+            "echo started; sleep 30",
+        ],
+        Duration::from_millis(2600),
+        |_| {},
+    );
+
+    assert!(
+        output_str.contains("[supi] Restart schedule: every 1s, next restart at "),
+        "Expected the schedule and first restart time in the banner. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Scheduled restart (every 1s)"),
+        "Expected a scheduled restart. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Next scheduled restart at "),
+        "Expected the next restart time to be logged. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.matches("started\r").count() >= 3,
+        "Expected the child to be restarted on every tick. Output:\n{}",
+        output_str
+    );
+}
+
+// Manual test: cargo run -- --restart-cron '*/5 * * * *' bash -- -c 'echo started; sleep 600'
+#[test]
+fn test_restart_cron_goes_through_debounce() {
+    // Fires every second, but the debounce only lets every other restart through
+    let output_str = run_supi(
+        &[
+            "--restart-cron",
+            "* * * * * *",
+            "--restart-debounce-ms",
+            "1500",
+            "--",
+            "bash",
+            "-c",
+            "echo started; sleep 30",
+        ],
+        Duration::from_millis(3500),
+        |_| {},
+    );
+
+    assert!(
+        output_str.contains("[supi] Restart schedule: cron '* * * * * *', next restart at "),
+        "Expected the cron pattern in the banner. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Scheduled restart (cron '* * * * * *')"),
+        "Expected a scheduled restart. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Restart request ignored (debounce active"),
+        "Expected scheduled restarts to be debounced. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Stopping child process gracefully..."),
+        "Expected scheduled restarts to stop the child gracefully. Output:\n{}",
+        output_str
+    );
+}
+
+// Test that a stopped child isn't started by the schedule
+#[test]
+fn test_scheduled_restart_skips_stopped_child() {
+    let output_str = run_supi(
+        &[
+            "--restart-every",
+            "500ms",
+            "--",
+            "bash",
+            "-c",
+            "echo started",
+        ],
+        Duration::from_millis(1500),
+        |_| {},
+    );
+
+    assert!(
+        output_str
+            .contains("[supi] Scheduled restart (every 500ms) skipped, child process not running"),
+        "Expected the scheduled restart to be skipped. Output:\n{}",
+        output_str
+    );
+    assert_eq!(
+        output_str.matches("started\r").count(),
+        1,
+        "Expected the exited child to stay stopped. Output:\n{}",
+        output_str
+    );
+}
+
+// Test that invalid schedules are rejected before the child starts
+#[test]
+fn test_invalid_schedules_are_rejected() {
+    let cases: [(&[&str], &str); 3] = [
+        (
+            &["--restart-every", "soon"],
+            "Invalid --restart-every 'soon'",
+        ),
+        (
+            &["--restart-cron", "0 3 * *"],
+            "Invalid --restart-cron '0 3 * *'",
+        ),
+        (
+            &["--restart-every", "1h", "--restart-cron", "0 3 * * *"],
+            "cannot be used with",
+        ),
+    ];
+
+    for (args, expected) in cases {
+        Command::cargo_bin("supi")
+            .unwrap()
+            .args(args)
+            .args(["--", "true"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(expected));
+    }
+}
+
+// Test that an interval too long to schedule is rejected instead of panicking
+#[test]
+fn test_huge_restart_every_is_rejected() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args(["--restart-every", "200000000000000d", "--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid --restart-every '200000000000000d': too large",
+        ))
+        .stderr(predicate::str::contains("panicked").not());
+}
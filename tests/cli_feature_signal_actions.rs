@@ -0,0 +1,125 @@
+// Feature: Signal-to-action mapping with support for all standard and realtime signals
+// Tests for --on-signal with hotkey actions, forward and realtime signals
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::output_after_signals;
+use predicates::prelude::*;
+
+// Manual test: cargo run -- --on-signal SIGUSR2=stop bash -- -c "echo started; sleep 30"
+//              (kill -USR2 <supi pid> stops the child, kill -USR1 starts it again)
+#[test]
+fn test_signal_mapped_to_hotkey_action() {
+    let output = output_after_signals(
+        &["--on-signal", "SIGUSR2=stop"],
+        "echo 'child started'; sleep 30",
+        &[libc::SIGUSR2, libc::SIGUSR1],
+    );
+
+    assert!(
+        output.contains("Signal actions: SIGINT=terminate, SIGTERM=terminate, SIGQUIT=terminate, SIGUSR1=restart, SIGUSR2=stop"),
+        "Expected the signal mapping on startup. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Received SIGUSR2 signal: stop"),
+        "Expected SIGUSR2 to stop the child. Output:\n{}",
+        output
+    );
+    assert_eq!(
+        output.matches("child started\r").count(),
+        2,
+        "Expected the restart signal to start the stopped child. Output:\n{}",
+        output
+    );
+}
+
+// Test that a signal mapped to quit names the signal, not a hotkey, when shutting down
+#[test]
+fn test_signal_mapped_to_quit_names_the_signal() {
+    let output = output_after_signals(
+        &["--on-signal", "SIGUSR2=quit"],
+        "echo 'child started'; sleep 30",
+        &[libc::SIGUSR2],
+    );
+
+    assert!(
+        output.contains("[supi] Received SIGUSR2 signal, shutting down..."),
+        "Expected the quit to name SIGUSR2. Output:\n{}",
+        output
+    );
+    assert!(
+        !output.contains("Quit requested") && !output.contains("hotkey pressed"),
+        "Expected no hotkey quit message. Output:\n{}",
+        output
+    );
+}
+
+// Test that several signals can be forwarded with one mapping
+#[test]
+fn test_forward_multiple_signals() {
+    let output = output_after_signals(
+        &["--on-signal", "SIGHUP,SIGWINCH=forward"],
+        "trap 'echo got hup' HUP; trap 'echo got winch' WINCH; while true; do sleep 0.1; done",
+        &[libc::SIGHUP, libc::SIGWINCH],
+    );
+
+    assert!(
+        output.contains("Received SIGHUP signal, forwarded to child process group"),
+        "Expected forwarding to be logged. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("got hup\r") && output.contains("got winch\r"),
+        "Expected the child to receive both signals. Output:\n{}",
+        output
+    );
+}
+
+// Test that realtime signals can be mapped
+#[cfg(target_os = "linux")]
+#[test]
+fn test_realtime_signal_restart() {
+    let output = output_after_signals(
+        &["--on-signal", "SIGRTMIN+3=restart"],
+        "echo 'child started'; sleep 30",
+        &[libc::SIGRTMIN() + 3],
+    );
+
+    assert!(
+        output.contains("Received SIGRTMIN+3 signal"),
+        "Expected the realtime signal to be recognized. Output:\n{}",
+        output
+    );
+    assert_eq!(
+        output.matches("child started\r").count(),
+        2,
+        "Expected SIGRTMIN+3 to restart the child. Output:\n{}",
+        output
+    );
+}
+
+// Test that uncatchable or unknown signals and unknown actions are rejected
+#[test]
+fn test_invalid_signal_mappings_are_rejected() {
+    let cases = [
+        ("SIGKILL=restart", "Signal SIGKILL can't be caught"),
+        ("SIGFOO=restart", "Unknown signal: SIGFOO"),
+        ("SIGHUP=explode", "Invalid signal action 'explode'"),
+        ("SIGHUP", "expected SIGNAL[,SIGNAL...]=ACTION"),
+        (
+            "SIGRTMIN+2147483647=restart",
+            "realtime signals range from SIGRTMIN",
+        ),
+    ];
+
+    for (spec, message) in cases {
+        let mut cmd = Command::cargo_bin("supi").unwrap();
+        cmd.args(["--on-signal", spec, "echo", "test"])
+            .timeout(std::time::Duration::from_secs(2))
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(message));
+    }
+}
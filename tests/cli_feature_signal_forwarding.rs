@@ -0,0 +1,97 @@
+// Feature: Transparent signal forwarding to the child process group
+// Tests for --forward-signal and the forwarding log messages
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::output_after_signals;
+use predicates::prelude::*;
+
+// Manual test: cargo run -- --forward-signal SIGHUP bash -- -c "trap 'echo reload' HUP; while true; do sleep 1; done"
+//              (kill -HUP <supi pid> prints 'reload' without restarting the child)
+#[test]
+fn test_forward_signal_to_child() {
+    let output = output_after_signals(
+        &["--forward-signal", "SIGHUP"],
+        "echo 'child started'; trap 'echo reloading config' HUP; while true; do sleep 0.1; done",
+        &[libc::SIGHUP, libc::SIGHUP],
+    );
+
+    assert_eq!(
+        output.matches("reloading config\r").count(),
+        2,
+        "Expected the child to receive both SIGHUPs. Output:\n{}",
+        output
+    );
+    assert_eq!(
+        output
+            .matches("Received SIGHUP signal, forwarded to child process group ")
+            .count(),
+        2,
+        "Expected every forwarded signal to be logged. Output:\n{}",
+        output
+    );
+    assert_eq!(
+        output.matches("child started\r").count(),
+        1,
+        "Expected forwarding not to restart the child. Output:\n{}",
+        output
+    );
+}
+
+// Test that a comma-separated list forwards each signal, including one supi owns by default
+#[test]
+fn test_forward_signal_list() {
+    let output = output_after_signals(
+        &["--forward-signal", "USR2,SIGUSR1"],
+        "trap 'echo got usr1' USR1; trap 'echo got usr2' USR2; while true; do sleep 0.1; done",
+        &[libc::SIGUSR2, libc::SIGUSR1],
+    );
+
+    assert!(
+        output.contains("got usr2\r"),
+        "Expected SIGUSR2 to be forwarded. Output:\n{}",
+        output
+    );
+    assert!(
+        output.contains("got usr1\r"),
+        "Expected SIGUSR1 to be forwarded instead of restarting. Output:\n{}",
+        output
+    );
+}
+
+// Test that forwarding without a running child is logged instead of failing
+#[test]
+fn test_forward_signal_without_child() {
+    let output = output_after_signals(
+        &["--forward-signal", "SIGHUP"],
+        "echo 'short lived'",
+        &[libc::SIGHUP],
+    );
+
+    assert!(
+        output.contains("Received SIGHUP signal, child process not running, nothing to forward"),
+        "Expected a log message for the dropped signal. Output:\n{}",
+        output
+    );
+}
+
+// Test that invalid signals are rejected
+#[test]
+fn test_invalid_forward_signal_is_rejected() {
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--forward-signal", "SIGNOPE", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid --forward-signal 'SIGNOPE'",
+        ));
+
+    let mut cmd = Command::cargo_bin("supi").unwrap();
+    cmd.args(["--forward-signal", "SIGSTOP", "echo", "test"])
+        .timeout(std::time::Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Signal SIGSTOP can't be caught"));
+}
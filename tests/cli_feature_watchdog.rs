@@ -0,0 +1,172 @@
+// Feature: Memory and CPU watchdog
+// Tests for --max-memory, --max-cpu, --max-cpu-for and --sample-interval
+
+mod cli_test_utils;
+
+use assert_cmd::Command;
+use cli_test_utils::run_supi;
+use predicates::prelude::*;
+use std::time::Duration;
+
+// Manual test: cargo run -- --max-cpu 50% --max-cpu-for 2s bash -- -c 'echo started; while :; do :; done'
+#[test]
+fn test_sustained_cpu_usage_restarts_child() {
+    let output_str = run_supi(
+        &[
+            "--max-cpu",
+            "50%",
+            "--max-cpu-for",
+            "500ms",
+            "--sample-interval",
+            "100ms",
+            "--",
+            "bash",
+            "-c",
+            "echo started; while :; do :; done",
+        ],
+        Duration::from_secs(3),
+        |_| {},
+    );
+
+    assert!(
+        output_str
+            .contains("[supi] Watchdog: restart when CPU > 50% for 500ms, sampled every 100ms"),
+        "Expected the watchdog settings in the banner. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("[supi] Watchdog: CPU above 50% for"),
+        "Expected the CPU limit to trigger a restart. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains("%, peak "),
+        "Expected the peak usage to be logged. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.matches("started\r").count() >= 2,
+        "Expected the child to be restarted. Output:\n{}",
+        output_str
+    );
+}
+
+// Manual test: cargo run -- --max-memory 40M bash -- -c 'x=$(head -c 80M /dev/zero | tr "\0" a); sleep 30'
+#[test]
+fn test_memory_limit_restarts_child_tree() {
+    // The memory is held by a grandchild, so the whole tree has to be summed
+    let output_str = run_supi(
+        &[
+            "--max-memory",
+            "40M",
+            "--sample-interval",
+            "100ms",
+            "--",
+            "bash",
+            "-c",
+            "echo started; bash -c 'x=$(head -c 80M /dev/zero | tr \"\\0\" a); sleep 30'",
+        ],
+        Duration::from_secs(4),
+        |_| {},
+    );
+
+    assert!(
+        output_str.contains("[supi] Watchdog: memory limit 40M exceeded (now "),
+        "Expected the memory limit to trigger a restart. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.contains(", peak "),
+        "Expected the peak memory to be logged. Output:\n{}",
+        output_str
+    );
+    assert!(
+        output_str.matches("started\r").count() >= 2,
+        "Expected the child to be restarted. Output:\n{}",
+        output_str
+    );
+}
+
+// Test that a child within its limits keeps running and its usage shows in the status
+#[test]
+fn test_status_shows_usage_within_limits() {
+    let output_str = run_supi(
+        &[
+            "--max-memory",
+            "1G",
+            "--max-cpu",
+            "400",
+            "--sample-interval",
+            "100ms",
+            "--",
+            "bash",
+            "-c",
+            "echo started; sleep 30",
+        ],
+        Duration::from_secs(1),
+        |supi| {
+            supi.type_keys(b"i");
+            std::thread::sleep(Duration::from_secs(1));
+        },
+    );
+
+    assert!(
+        output_str.contains("memory (peak ") && output_str.contains("% CPU (peak "),
+        "Expected the status to show memory and CPU usage. Output:\n{}",
+        output_str
+    );
+    assert!(
+        !output_str.contains("restarting run #"),
+        "Expected no restart within the limits. Output:\n{}",
+        output_str
+    );
+    assert_eq!(
+        output_str.matches("started\r").count(),
+        1,
+        "Expected a single run. Output:\n{}",
+        output_str
+    );
+}
+
+// Test that invalid watchdog values are rejected before the child starts
+#[test]
+fn test_invalid_watchdog_values_are_rejected() {
+    let cases: [(&[&str], &str); 3] = [
+        (&["--max-memory", "1X"], "Invalid --max-memory '1X'"),
+        (&["--max-cpu", "lots"], "Invalid --max-cpu 'lots'"),
+        (
+            &["--max-cpu", "90", "--sample-interval", "0s"],
+            "Invalid --sample-interval '0s'",
+        ),
+    ];
+
+    for (args, expected) in cases {
+        Command::cargo_bin("supi")
+            .unwrap()
+            .args(args)
+            .args(["--", "true"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(expected));
+    }
+}
+
+// Test that a sample interval too long to schedule is rejected instead of panicking
+#[test]
+fn test_huge_sample_interval_is_rejected() {
+    Command::cargo_bin("supi")
+        .unwrap()
+        .args([
+            "--max-memory",
+            "1G",
+            "--sample-interval",
+            "200000000000000d",
+        ])
+        .args(["--", "true"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid --sample-interval '200000000000000d': too large",
+        ))
+        .stderr(predicate::str::contains("panicked").not());
+}
@@ -78,7 +78,7 @@ fn test_simple_echo() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--stop-on-child-exit", "echo", "hello world"]);
+    cmd.args(["--stop-on-child-exit", "echo", "hello world"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     drop(pair.slave);
@@ -106,7 +106,7 @@ fn test_stop_on_child_exit_flag() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--stop-on-child-exit", "echo", "test message"]);
+    cmd.args(["--stop-on-child-exit", "echo", "test message"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     drop(pair.slave);
@@ -139,7 +139,7 @@ fn test_stdout_forwarding() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--stop-on-child-exit",
         "--",
         "bash",
@@ -183,7 +183,7 @@ fn test_stderr_forwarding() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--stop-on-child-exit",
         "--",
         "bash",
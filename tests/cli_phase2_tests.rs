@@ -14,7 +14,7 @@ fn test_sigterm_graceful_shutdown() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--", "sleep", "30"]);
+    cmd.args(["--", "sleep", "30"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     let child_pid = child.process_id().unwrap();
@@ -54,7 +54,7 @@ fn test_sigint_graceful_shutdown() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--", "sleep", "30"]);
+    cmd.args(["--", "sleep", "30"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     let child_pid = child.process_id().unwrap();
@@ -94,7 +94,7 @@ fn test_restart_signal() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--", "bash", "-c", "echo 'started'; sleep 10"]);
+    cmd.args(["--", "bash", "-c", "echo 'started'; sleep 10"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     let child_pid = child.process_id().unwrap();
@@ -160,8 +160,11 @@ sleep 30
         std::fs::set_permissions(temp_file.path(), perms).unwrap();
     }
 
+    // Close our write handle so exec doesn't fail with ETXTBSY on Linux
+    let temp_path = temp_file.into_temp_path();
+
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.arg(temp_file.path().to_str().unwrap());
+    cmd.arg(temp_path.to_str().unwrap());
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     let child_pid = child.process_id().unwrap();
@@ -15,7 +15,7 @@ fn test_default_hotkey_restart() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--", "bash", "-c", "echo 'Process started'; sleep 10"]);
+    cmd.args(["--", "bash", "-c", "echo 'Process started'; sleep 10"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     drop(pair.slave);
@@ -60,7 +60,7 @@ fn test_custom_hotkey_restart() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--restart-hotkey",
         "x",
         "--",
@@ -122,7 +122,7 @@ fn test_non_hotkey_characters_ignored() {
     let script = format!("echo '{}'; sleep 5", marker);
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--", "bash", "-c", &script]);
+    cmd.args(["--", "bash", "-c", &script]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     drop(pair.slave);
@@ -174,7 +174,7 @@ fn test_hotkey_with_stop_on_child_exit() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--restart-hotkey",
         "r",
         "--stop-on-child-exit",
@@ -209,7 +209,7 @@ fn test_restart_with_stop_on_child_exit() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--stop-on-child-exit",
         "--",
         "bash",
@@ -14,7 +14,7 @@ fn test_pty_long_running_process_with_hotkey() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--", "bash", "-c", "echo 'Process started'; sleep 30"]);
+    cmd.args(["--", "bash", "-c", "echo 'Process started'; sleep 30"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     drop(pair.slave);
@@ -58,7 +58,7 @@ fn test_pty_process_exits_immediately() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--stop-on-child-exit", "echo", "quick exit"]);
+    cmd.args(["--stop-on-child-exit", "echo", "quick exit"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     drop(pair.slave);
@@ -91,7 +91,7 @@ fn test_pty_continuous_output() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--",
         "bash",
         "-c",
@@ -133,7 +133,7 @@ fn test_pty_process_ignores_sigterm() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--",
         "bash",
         "-c",
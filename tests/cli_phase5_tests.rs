@@ -19,7 +19,7 @@ fn test_silent_flag_suppresses_supervisor_output() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--silent", "--stop-on-child-exit", "echo", "child output"]);
+    cmd.args(["--silent", "--stop-on-child-exit", "echo", "child output"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     drop(pair.slave);
@@ -56,7 +56,7 @@ fn test_silent_flag_preserves_child_output() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--silent",
         "--stop-on-child-exit",
         "bash",
@@ -105,7 +105,7 @@ fn test_without_silent_flag_shows_supervisor_output() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&["--stop-on-child-exit", "echo", "child output"]);
+    cmd.args(["--stop-on-child-exit", "echo", "child output"]);
 
     let mut child = pair.slave.spawn_command(cmd).unwrap();
     drop(pair.slave);
@@ -152,7 +152,7 @@ fn test_log_color_flag() {
         let (pair, _output, reader_thread) = create_pty_with_reader();
 
         let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-        cmd.args(&["--log-color", color, "--stop-on-child-exit", "echo", "test"]);
+        cmd.args(["--log-color", color, "--stop-on-child-exit", "echo", "test"]);
 
         let mut child = pair.slave.spawn_command(cmd).unwrap();
         drop(pair.slave);
@@ -169,7 +169,7 @@ fn test_log_color_flag() {
 
     // Test invalid color - should fail
     let mut cmd = Command::cargo_bin("supi").unwrap();
-    cmd.args(&[
+    cmd.args([
         "--log-color",
         "invalid_color",
         "--stop-on-child-exit",
@@ -188,7 +188,7 @@ fn test_colored_output() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--log-color",
         "yellow",
         "--info-color",
@@ -235,7 +235,7 @@ fn test_no_color_option() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--log-color",
         "none",
         "--info-color",
@@ -297,7 +297,7 @@ fn test_different_colors_produce_different_codes() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--log-color",
         "red",
         "--stop-on-child-exit",
@@ -333,7 +333,7 @@ fn test_info_color_independent() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--log-color",
         "red",
         "--info-color",
@@ -378,7 +378,7 @@ fn test_debounce_disabled_allows_rapid_restarts() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--restart-debounce-ms",
         "0",
         "--restart-signal",
@@ -441,7 +441,7 @@ fn test_debounce_prevents_rapid_restarts() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--restart-debounce-ms",
         "500", // 500ms debounce
         "--restart-signal",
@@ -511,7 +511,7 @@ fn test_debounce_allows_restart_after_window_expires() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--restart-debounce-ms",
         "200", // 200ms debounce
         "--restart-signal",
@@ -586,7 +586,7 @@ fn test_debounce_affects_hotkey_restarts() {
     let (pair, output, reader_thread) = create_pty_with_reader();
 
     let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
-    cmd.args(&[
+    cmd.args([
         "--restart-debounce-ms",
         "500",
         "--restart-hotkey",
@@ -1,9 +1,13 @@
-use portable_pty::{native_pty_system, PtyPair, PtySize};
-use std::io::Read;
+// Shared by the test files through `mod cli_test_utils;`, each uses only some helpers
+#![allow(dead_code)]
+
+use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtyPair, PtySize};
+use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Helper function to create PTY test environment
-/// 
+///
 /// Returns a tuple of:
 /// - PtyPair: The PTY master/slave pair
 /// - Arc<Mutex<Vec<u8>>>: Shared buffer containing all output from the PTY
@@ -12,11 +16,7 @@ use std::sync::{Arc, Mutex};
 /// All tests use a PTY setup for testing to avoid display issues in cargo output
 /// due to the use of raw tty mode in hotkey.rs. PTY provides a clean, realistic
 /// terminal environment for testing.
-pub fn create_pty_with_reader() -> (
-    PtyPair,
-    Arc<Mutex<Vec<u8>>>,
-    std::thread::JoinHandle<()>,
-) {
+pub fn create_pty_with_reader() -> (PtyPair, Arc<Mutex<Vec<u8>>>, std::thread::JoinHandle<()>) {
     let pty_system = native_pty_system();
     let pair = pty_system
         .openpty(PtySize {
@@ -46,3 +46,95 @@ pub fn create_pty_with_reader() -> (
     (pair, output, reader_thread)
 }
 
+/// A supi process running in a PTY, handed to the `interact` callback of [`run_supi`]
+pub struct PtySupi {
+    master: Box<dyn MasterPty + Send>,
+    writer: Option<Box<dyn Write + Send>>,
+    pid: i32,
+}
+
+impl PtySupi {
+    /// Type keys into the terminal supi is attached to
+    pub fn type_keys(&mut self, keys: &[u8]) {
+        let master = &self.master;
+        let writer = self
+            .writer
+            .get_or_insert_with(|| master.take_writer().unwrap());
+        writer.write_all(keys).unwrap();
+        writer.flush().unwrap();
+    }
+
+    /// Send a signal to the supi process
+    pub fn signal(&self, signal: i32) {
+        unsafe {
+            libc::kill(self.pid, signal);
+        }
+    }
+}
+
+/// Run supi with the given args in a PTY. After `startup`, `interact` can type keys and
+/// send signals; then supi gets SIGTERM and everything it printed is returned.
+pub fn run_supi(args: &[&str], startup: Duration, interact: impl FnOnce(&mut PtySupi)) -> String {
+    let (pair, output, reader_thread) = create_pty_with_reader();
+
+    let mut cmd = CommandBuilder::new(env!("CARGO_BIN_EXE_supi"));
+    cmd.args(args);
+
+    let mut child = pair.slave.spawn_command(cmd).unwrap();
+    let mut supi = PtySupi {
+        master: pair.master,
+        writer: None,
+        pid: child.process_id().unwrap() as i32,
+    };
+    drop(pair.slave);
+
+    std::thread::sleep(startup);
+    interact(&mut supi);
+    supi.writer = None;
+
+    supi.signal(libc::SIGTERM);
+    let _ = child.wait();
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output_str = String::from_utf8_lossy(&output.lock().unwrap()).to_string();
+    let _ = reader_thread.join();
+    output_str
+}
+
+/// The args to run supi with `supi_args` and a `bash -c <script>` child
+fn bash_args<'a>(supi_args: &[&'a str], script: &'a str) -> Vec<&'a str> {
+    let mut args = supi_args.to_vec();
+    args.extend(["--", "bash", "-c", script]);
+    args
+}
+
+/// Start supi with the given args and child script, type the inputs 300ms apart
+/// and return the output
+pub fn output_after_typing(supi_args: &[&str], script: &str, inputs: &[&[u8]]) -> String {
+    run_supi(
+        &bash_args(supi_args, script),
+        Duration::from_secs(1),
+        |supi| {
+            for input in inputs {
+                supi.type_keys(input);
+                std::thread::sleep(Duration::from_millis(300));
+            }
+            std::thread::sleep(Duration::from_millis(700));
+        },
+    )
+}
+
+/// Start supi with the given args and child script, send the signals to supi
+/// 700ms apart and return the output
+pub fn output_after_signals(supi_args: &[&str], script: &str, signals: &[i32]) -> String {
+    run_supi(
+        &bash_args(supi_args, script),
+        Duration::from_secs(1),
+        |supi| {
+            for signal in signals {
+                supi.signal(*signal);
+                std::thread::sleep(Duration::from_millis(700));
+            }
+        },
+    )
+}
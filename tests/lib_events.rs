@@ -0,0 +1,206 @@
+// Feature: Supervisor events
+// Tests for SupervisorEvent subscriptions on the Supervisor and its handle
+
+use std::time::Duration;
+use supi_cli::{RestartSource, StopReason, Stream, SupervisorBuilder, SupervisorEvent};
+use tokio::sync::broadcast::Receiver;
+
+/// A quiet builder for `sh -c <script>` without hotkeys or restart debounce
+fn builder(script: &str) -> SupervisorBuilder {
+    SupervisorBuilder::new("sh")
+        .args(["-c", script])
+        .hotkeys(false)
+        .silent(true)
+        .restart_debounce_ms(0)
+}
+
+/// Receive events until one matches, returning it
+async fn wait_for(
+    events: &mut Receiver<SupervisorEvent>,
+    matches: impl Fn(&SupervisorEvent) -> bool,
+) -> SupervisorEvent {
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let event = events.recv().await.expect("Event channel closed");
+            if matches(&event) {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("Timed out waiting for an event")
+}
+
+// Manual test: see the events example in the README library section
+#[tokio::test]
+async fn test_spawn_ready_and_output_events() {
+    let mut supervisor = builder("echo hello; echo oops >&2; sleep 30")
+        .build()
+        .unwrap();
+    let handle = supervisor.handle();
+    let mut events = supervisor.subscribe();
+    let run = tokio::spawn(async move { supervisor.run().await });
+
+    let spawned = wait_for(&mut events, |e| {
+        matches!(e, SupervisorEvent::Spawned { .. })
+    })
+    .await;
+    let SupervisorEvent::Spawned { run: 1, pid } = spawned else {
+        panic!("Expected the first run to spawn, got: {:?}", spawned);
+    };
+    assert_eq!(handle.status().await.unwrap().pid, Some(pid));
+
+    wait_for(&mut events, |e| matches!(e, SupervisorEvent::Ready)).await;
+    wait_for(&mut events, |e| {
+        *e == SupervisorEvent::Output {
+            run: 1,
+            stream: Stream::Stdout,
+            line: "hello".to_string(),
+        }
+    })
+    .await;
+
+    // Output can arrive before Ready, so subscribe again for stderr
+    let mut stderr_events = handle.subscribe();
+    handle.restart().await.unwrap();
+    wait_for(&mut stderr_events, |e| {
+        *e == SupervisorEvent::Output {
+            run: 2,
+            stream: Stream::Stderr,
+            line: "oops".to_string(),
+        }
+    })
+    .await;
+
+    handle.stop().await.unwrap();
+    run.await.unwrap().unwrap();
+}
+
+// Test that a requested restart reports the request, the stop and the new run
+#[tokio::test]
+async fn test_restart_events() {
+    let mut supervisor = builder("sleep 30").build().unwrap();
+    let handle = supervisor.handle();
+    let mut events = supervisor.subscribe();
+    let run = tokio::spawn(async move { supervisor.run().await });
+
+    wait_for(&mut events, |e| matches!(e, SupervisorEvent::Ready)).await;
+    handle.restart().await.unwrap();
+
+    let mut received = Vec::new();
+    while !matches!(
+        received.last(),
+        Some(SupervisorEvent::Spawned { run: 2, .. })
+    ) {
+        received.push(wait_for(&mut events, |_| true).await);
+    }
+    assert!(matches!(
+        received.as_slice(),
+        [
+            SupervisorEvent::RestartRequested {
+                source: RestartSource::Request
+            },
+            SupervisorEvent::Stopping {
+                run: 1,
+                reason: StopReason::Restart,
+                ..
+            },
+            SupervisorEvent::Exited {
+                run: 1,
+                stopped: Some(StopReason::Restart),
+                ..
+            },
+            SupervisorEvent::Spawned { run: 2, .. },
+        ]
+    ));
+
+    handle.stop().await.unwrap();
+    let exited = wait_for(&mut events, |e| matches!(e, SupervisorEvent::Exited { .. })).await;
+    assert!(matches!(
+        exited,
+        SupervisorEvent::Exited {
+            run: 2,
+            stopped: Some(StopReason::Stop),
+            ..
+        }
+    ));
+    run.await.unwrap().unwrap();
+}
+
+// Test that a restart inside the debounce window is reported as debounced
+#[tokio::test]
+async fn test_debounced_restart_event() {
+    let mut supervisor = builder("sleep 30")
+        .restart_debounce_ms(60_000)
+        .build()
+        .unwrap();
+    let handle = supervisor.handle();
+    let mut events = supervisor.subscribe();
+    let run = tokio::spawn(async move { supervisor.run().await });
+
+    wait_for(&mut events, |e| matches!(e, SupervisorEvent::Ready)).await;
+    handle.restart().await.unwrap();
+    handle.restart().await.unwrap();
+
+    let debounced = wait_for(&mut events, |e| {
+        matches!(e, SupervisorEvent::Debounced { .. })
+    })
+    .await;
+    let SupervisorEvent::Debounced { remaining } = debounced else {
+        unreachable!();
+    };
+    assert!(
+        remaining > Duration::from_secs(50),
+        "Unexpected remaining debounce: {:?}",
+        remaining
+    );
+    assert_eq!(handle.status().await.unwrap().run, 2);
+
+    handle.stop().await.unwrap();
+    run.await.unwrap().unwrap();
+}
+
+// Test that a child stopped at --max-runtime is told apart from a normal exit
+#[tokio::test]
+async fn test_max_runtime_events() {
+    let mut supervisor = builder("exec sleep 30")
+        .max_runtime("1s")
+        .stop_on_child_exit(true)
+        .build()
+        .unwrap();
+    let mut events = supervisor.subscribe();
+    let run = tokio::spawn(async move { supervisor.run().await });
+
+    let stopping = wait_for(&mut events, |e| {
+        matches!(e, SupervisorEvent::Stopping { .. })
+    })
+    .await;
+    assert!(matches!(
+        stopping,
+        SupervisorEvent::Stopping {
+            run: 1,
+            reason: StopReason::MaxRuntime,
+            ..
+        }
+    ));
+    let exited = wait_for(&mut events, |e| matches!(e, SupervisorEvent::Exited { .. })).await;
+    assert!(
+        matches!(
+            exited,
+            SupervisorEvent::Exited {
+                run: 1,
+                stopped: Some(StopReason::MaxRuntime),
+                ..
+            }
+        ),
+        "Unexpected event: {:?}",
+        exited
+    );
+
+    let exit_code = tokio::time::timeout(Duration::from_secs(10), run)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(exit_code, 124);
+}
@@ -0,0 +1,181 @@
+// Feature: Supervisor library
+// Tests for SupervisorBuilder, SupervisorHandle and the typed errors of the supi_cli crate
+
+use std::time::Duration;
+use supi_cli::{Error, SupervisorBuilder, SupervisorHandle};
+
+/// A quiet builder for `sh -c <script>` without hotkeys or restart debounce
+fn builder(script: &str) -> SupervisorBuilder {
+    SupervisorBuilder::new("sh")
+        .args(["-c", script])
+        .hotkeys(false)
+        .silent(true)
+        .restart_debounce_ms(0)
+}
+
+/// Wait until the supervised child runs `run` times, returning its PID
+async fn wait_for_run(handle: &SupervisorHandle, run: u64) -> u32 {
+    for _ in 0..50 {
+        let status = handle.status().await.unwrap();
+        if status.run == run {
+            if let Some(pid) = status.pid {
+                return pid;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Run #{} didn't start", run);
+}
+
+// Manual test: see the example in the crate docs (cargo doc --open)
+#[tokio::test]
+async fn test_handle_restarts_queries_and_stops() {
+    let mut supervisor = builder("sleep 30").build().unwrap();
+    let handle = supervisor.handle();
+    let run = tokio::spawn(async move { supervisor.run().await });
+
+    let first_pid = wait_for_run(&handle, 1).await;
+    let status = handle.status().await.unwrap();
+    assert!(
+        status.is_running(),
+        "Expected the child to run: {:?}",
+        status
+    );
+    assert!(!status.paused);
+
+    handle.restart().await.unwrap();
+    let second_pid = wait_for_run(&handle, 2).await;
+    assert_ne!(
+        first_pid, second_pid,
+        "Expected a new child after the restart"
+    );
+
+    handle.stop().await.unwrap();
+    let exit_code = tokio::time::timeout(Duration::from_secs(10), run)
+        .await
+        .expect("Expected run() to return after stop")
+        .unwrap()
+        .unwrap();
+    assert_eq!(exit_code, 0);
+
+    assert!(
+        matches!(handle.status().await, Err(Error::Stopped)),
+        "Expected requests to fail once the supervisor stopped"
+    );
+}
+
+// Test that invalid settings are reported as typed errors by build(), naming the
+// setting and the rejected value
+#[tokio::test]
+async fn test_build_rejects_invalid_options() {
+    let cases = [
+        (
+            builder("true").restart_signal("SIGNOPE"),
+            "restart_signal",
+            "SIGNOPE",
+            "Unknown signal",
+        ),
+        (
+            builder("true").max_runtime("soon"),
+            "max_runtime",
+            "soon",
+            "expected a duration",
+        ),
+        (
+            builder("true").binds(["r=restart", "x=fly"]),
+            "binds",
+            "x=fly",
+            "Invalid action 'fly'",
+        ),
+        (
+            builder("true").hide(["ok", "("]),
+            "hide",
+            "(",
+            "Invalid regex",
+        ),
+        (
+            builder("true").limits(["nofile=64", "files=3"]),
+            "limits",
+            "files=3",
+            "unknown resource 'files'",
+        ),
+    ];
+
+    for (builder, expected_option, expected_value, reason) in cases {
+        match builder.build() {
+            Err(Error::InvalidOption {
+                option,
+                value,
+                source,
+            }) => {
+                assert_eq!(option, expected_option);
+                assert_eq!(value, expected_value);
+                assert!(
+                    source.to_string().contains(reason),
+                    "Expected '{}' in: {}",
+                    reason,
+                    source
+                );
+            }
+            Err(e) => panic!("Expected an invalid option error, got: {:?}", e),
+            Ok(_) => panic!(
+                "Expected {} '{}' to be rejected",
+                expected_option, expected_value
+            ),
+        }
+    }
+
+    assert!(
+        matches!(
+            "ctrl+".parse::<supi_cli::KeySequence>(),
+            Err(Error::InvalidValue { kind: "hotkey", .. })
+        ),
+        "Expected an invalid hotkey to be rejected"
+    );
+}
+
+// Test that a missing command is a spawn error and run() reports it
+#[tokio::test]
+async fn test_missing_command_is_a_spawn_error() {
+    let mut supervisor = SupervisorBuilder::new("definitely-not-a-command-supi")
+        .hotkeys(false)
+        .silent(true)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        supervisor.check_command(),
+        Err(Error::Spawn { .. })
+    ));
+    match supervisor.run().await {
+        Err(Error::Spawn { command, source }) => {
+            assert_eq!(command, "definitely-not-a-command-supi");
+            assert!(
+                source.to_string().contains("No such file"),
+                "Unexpected source: {}",
+                source
+            );
+        }
+        other => panic!("Expected a spawn error, got: {:?}", other),
+    }
+}
+
+// Test managing a child with just the process manager, without the event loop
+#[tokio::test]
+async fn test_process_manager_without_supervisor() {
+    let mut process_manager = builder("exit 3").build_process_manager().unwrap();
+
+    assert!(matches!(
+        process_manager.send_signal(libc::SIGTERM),
+        Err(Error::NotRunning)
+    ));
+
+    process_manager.spawn().await.unwrap();
+    assert!(process_manager.is_running());
+    assert_eq!(process_manager.run(), 1);
+
+    let status = process_manager.wait().await.unwrap();
+    assert_eq!(status.code(), Some(3));
+    assert_eq!(process_manager.last_exit_code(), Some(3));
+    assert!(!process_manager.is_running());
+}